@@ -0,0 +1,221 @@
+use crate::command::QueenCommand;
+use crate::context::Context;
+use crate::task::Task;
+
+/// Actions completing within a turn let the tree pick the next one, at most this many times.
+const MAX_TICKS_PER_TURN: usize = 3;
+
+/// Result of ticking a node.
+pub enum Status {
+    Success,
+    Failure,
+    /// the node is busy and the queen carries out the command this turn
+    Running(QueenCommand),
+}
+
+/**
+ * Node of a behavior tree, ticked once per turn from the root. Nodes running an action push
+ * their name to the path, innermost first, so the active branch can be printed.
+ **/
+pub trait Node {
+    fn tick(&mut self, context: &Context, path: &mut Vec<&'static str>) -> Status;
+}
+
+/**
+ * Ticks the children in order until one of them fails or runs, succeeds if all succeed.
+ * A sequence with memory continues at the child that ran last instead of starting over,
+ * for steps that must not be repeated once done.
+ **/
+pub struct Sequence {
+    name: &'static str,
+    children: Vec<Box<dyn Node>>,
+    memory: bool,
+    /// child to start at on the next tick
+    current: usize,
+}
+
+/// Task factory of an action node, None if there is nothing to do.
+type TaskFactory = Box<dyn Fn(&Context) -> Option<Box<dyn Task>>>;
+
+/// Ticks the children in order until one of them succeeds or runs, fails if all fail.
+pub struct Selector {
+    name: &'static str,
+    children: Vec<Box<dyn Node>>,
+}
+
+/// Swaps success and failure of its child.
+pub struct Inverter {
+    child: Box<dyn Node>,
+}
+
+/// Succeeds if the predicate holds, fails otherwise.
+pub struct Condition {
+    predicate: Box<dyn Fn(&Context) -> bool>,
+}
+
+/**
+ * Leaf running a task: the task is created by the factory when the node is ticked without one,
+ * the node fails if the factory has none. The node runs until the task is done and then succeeds,
+ * a new task runs for at least one turn like tasks of the bot.
+ * A task interrupted by another branch is resumed when the node is ticked again.
+ **/
+pub struct TaskAction {
+    name: &'static str,
+    factory: TaskFactory,
+    task: Option<Box<dyn Task>>,
+}
+
+/// Root of a behavior tree with the path of the node that ran last.
+pub struct BehaviorTree {
+    root: Box<dyn Node>,
+    active_path: Vec<&'static str>,
+}
+
+impl Sequence {
+    pub fn new(name: &'static str, children: Vec<Box<dyn Node>>) -> Sequence {
+        Sequence { name, children, memory: false, current: 0 }
+    }
+
+    pub fn with_memory(name: &'static str, children: Vec<Box<dyn Node>>) -> Sequence {
+        Sequence { name, children, memory: true, current: 0 }
+    }
+}
+
+impl Node for Sequence {
+    fn tick(&mut self, context: &Context, path: &mut Vec<&'static str>) -> Status {
+        let start = if self.memory { self.current } else { 0 };
+
+        for index in start..self.children.len() {
+            match self.children[index].tick(context, path) {
+                Status::Success => {},
+                Status::Failure => {
+                    self.current = 0;
+                    return Status::Failure;
+                },
+                Status::Running(command) => {
+                    self.current = index;
+                    path.push(self.name);
+                    return Status::Running(command);
+                },
+            }
+        }
+
+        self.current = 0;
+        Status::Success
+    }
+}
+
+impl Selector {
+    pub fn new(name: &'static str, children: Vec<Box<dyn Node>>) -> Selector {
+        Selector { name, children }
+    }
+}
+
+impl Node for Selector {
+    fn tick(&mut self, context: &Context, path: &mut Vec<&'static str>) -> Status {
+        for child in &mut self.children {
+            match child.tick(context, path) {
+                Status::Success => return Status::Success,
+                Status::Failure => {},
+                Status::Running(command) => {
+                    path.push(self.name);
+                    return Status::Running(command);
+                },
+            }
+        }
+
+        Status::Failure
+    }
+}
+
+impl Inverter {
+    pub fn new(child: Box<dyn Node>) -> Inverter {
+        Inverter { child }
+    }
+}
+
+impl Node for Inverter {
+    fn tick(&mut self, context: &Context, path: &mut Vec<&'static str>) -> Status {
+        match self.child.tick(context, path) {
+            Status::Success => Status::Failure,
+            Status::Failure => Status::Success,
+            running => running,
+        }
+    }
+}
+
+impl Condition {
+    pub fn new(predicate: impl Fn(&Context) -> bool + 'static) -> Condition {
+        Condition { predicate: Box::new(predicate) }
+    }
+}
+
+impl Node for Condition {
+    fn tick(&mut self, context: &Context, _path: &mut Vec<&'static str>) -> Status {
+        if (self.predicate)(context) { Status::Success } else { Status::Failure }
+    }
+}
+
+impl TaskAction {
+    pub fn new(name: &'static str, factory: impl Fn(&Context) -> Option<Box<dyn Task>> + 'static) -> TaskAction {
+        TaskAction {
+            name,
+            factory: Box::new(factory),
+            task: None,
+        }
+    }
+}
+
+impl Node for TaskAction {
+    fn tick(&mut self, context: &Context, path: &mut Vec<&'static str>) -> Status {
+        if self.task.as_ref().is_some_and(|task| task.is_task_done(context)) {
+            self.task = None;
+            return Status::Success;
+        }
+
+        if self.task.is_none() {
+            self.task = (self.factory)(context);
+        }
+
+        match &self.task {
+            Some(task) => {
+                path.push(self.name);
+                Status::Running(task.get_next_command(context))
+            },
+            None => Status::Failure,
+        }
+    }
+}
+
+impl BehaviorTree {
+    pub fn new(root: Box<dyn Node>) -> BehaviorTree {
+        BehaviorTree {
+            root,
+            active_path: Vec::new(),
+        }
+    }
+
+    /// Command of the node running this turn, waits if no node runs.
+    pub fn tick(&mut self, context: &Context) -> QueenCommand {
+        for _ in 0..MAX_TICKS_PER_TURN {
+            let mut path = Vec::new();
+            let status = self.root.tick(context, &mut path);
+
+            path.reverse();
+            self.active_path = path;
+
+            match status {
+                Status::Running(command) => return command,
+                Status::Success => continue,  // an action just completed
+                Status::Failure => break,
+            }
+        }
+
+        QueenCommand::Wait
+    }
+
+    /// Names of the nodes from the root down to the action that ran last, e.g. `queen/expand/build mine`.
+    pub fn active_path(&self) -> String {
+        self.active_path.join("/")
+    }
+}
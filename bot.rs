@@ -0,0 +1,147 @@
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+use crate::command::{QueenCommand, TrainCommand};
+use crate::context::Context;
+use crate::plan::Plan;
+use crate::protocol::{parse_init, parse_turn, ProtocolError, RecordingReader};
+use crate::strategy::Strategy;
+use crate::task::{Task, WaitTask};
+use crate::timer::TurnTimer;
+
+/// Decision state kept between turns.
+pub struct Bot {
+    pub context: Context,
+    task: Box<dyn Task>,
+    /// tasks preempted by more urgent ones, resumed last in first out
+    suspended: Vec<Box<dyn Task>>,
+    /// tasks following the current one
+    plan: Plan,
+    strategy: Box<dyn Strategy>,
+    first_iteration: bool,
+}
+
+impl Bot {
+    pub fn new(context: Context, strategy: Box<dyn Strategy>) -> Bot {
+        let plan = Plan::new(&context, Vec::new());
+
+        Bot {
+            context,
+            task: Box::new(WaitTask::new()),
+            suspended: Vec::new(),
+            plan,
+            strategy,
+            first_iteration: true,
+        }
+    }
+
+    /// Decides the commands of a turn, the input of the turn must already be parsed into the context.
+    pub fn play_turn(&mut self, timer: &TurnTimer) -> (QueenCommand, TrainCommand) {
+        let context = &mut self.context;
+        context.record_turn();
+
+        if self.first_iteration {
+            context.detect_start_positions();
+            self.first_iteration = false;
+        }
+
+        if let Some(next_strategy) = self.strategy.get_next_strategy(context) {
+            self.strategy = next_strategy;
+            self.task = Box::new(WaitTask::new());  // select a task of the new strategy
+            self.plan = Plan::new(context, Vec::new());
+            self.suspended.clear();
+        }
+
+        // the current task keeps running, it aborts itself if needed
+        if self.plan.is_outdated(context) {
+            self.plan = Plan::new(context, Vec::new());
+        }
+
+        if let Some(urgent_task) = self.strategy.get_urgent_task(context) {
+            if urgent_task.priority() > self.task.priority() {
+                let preempted = std::mem::replace(&mut self.task, urgent_task);
+                self.suspended.push(preempted);
+            }
+        }
+
+        if self.task.is_task_done(context) {
+            self.task = self.next_task(timer);
+        }
+
+        let context = &self.context;
+        (self.task.get_next_command(context), self.strategy.get_train_command(context))
+    }
+
+    /// Resumes the last preempted task, then continues with the plan or plans anew.
+    fn next_task(&mut self, timer: &TurnTimer) -> Box<dyn Task> {
+        let context = &self.context;
+
+        while let Some(task) = self.suspended.pop() {
+            if !task.is_task_done(context) {
+                return task;
+            }
+        }
+
+        if let Some(task) = self.plan.next_task(context) {
+            return task;
+        }
+
+        let mut tasks = self.strategy.get_next_plan(context, timer);
+        let task = tasks.remove(0);
+        self.plan = Plan::new(context, tasks);
+        task
+    }
+}
+
+/**
+ * Runs the bot until the input ends, the input lines of each turn are dumped to stderr if requested.
+ * Turns taking longer than the time warning, if any, are reported on stderr.
+ **/
+pub fn run_bot(
+    input: impl BufRead,
+    output: &mut impl Write,
+    strategy: Box<dyn Strategy>,
+    dump_input: bool,
+    time_warning: Option<Duration>,
+) -> Result<(), ProtocolError> {
+    let mut input = RecordingReader::new(input);
+    let mut bot = Bot::new(parse_init(&mut input)?, strategy);
+    let mut timer = TurnTimer::new();
+
+    // game loop
+    loop {
+        // the response time counts from the arrival of the input, parsing it is part of the turn
+        input.fill_buf()?;
+        timer.start_turn();
+
+        if !parse_turn(&mut input, &mut bot.context)? {
+            break;
+        }
+
+        let (queen_command, train_command) = bot.play_turn(&timer);
+
+        writeln!(output, "{}", queen_command).unwrap();
+        writeln!(output, "{}", train_command).unwrap();
+
+        let elapsed = timer.elapsed();
+
+        if time_warning.is_some_and(|time_warning| elapsed > time_warning) {
+            eprintln!("turn {} took {} ms", bot.context.history.turn_count() - 1, elapsed.as_millis());
+        }
+
+        let input_lines = input.take_lines();
+
+        if dump_input {
+            for line in &input_lines {
+                eprint!("{};", line);
+            }
+            eprintln!();
+
+            if let Some(info) = bot.strategy.get_debug_info() {
+                eprintln!("{}", info);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use code_royale::bundler::bundle;
+
+/**
+ * Flattens the bot binary and the library modules it uses into a single file for submission,
+ * see `bundler::bundle`. The size of the bundle is reported on stderr.
+ * Usage: bundle [main.rs] > submission.rs
+ **/
+fn main() {
+    let crate_name = env!("CARGO_PKG_NAME").replace('-', "_");
+    let main_path = match env::args().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("main.rs"),
+    };
+
+    match bundle(&main_path, &crate_name) {
+        Ok(source) => {
+            print!("{}", source);
+            eprintln!("bundled {} characters", source.chars().count());
+        },
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    }
+}
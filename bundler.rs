@@ -0,0 +1,385 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters CodinGame accepts for a submission.
+pub const MAX_SUBMISSION_CHARS: usize = 100_000;
+
+/**
+ * Flattens the bot binary and the library modules it uses into a single source file. Only modules
+ * reachable from the binary through `code_royale::` and `crate::` paths are included. Comments,
+ * whitespace not separating tokens and items gated by `#[cfg(test)]` or a cargo feature are left
+ * out, since the submission is compiled without features. Fails if the result exceeds the size
+ * CodinGame accepts.
+ **/
+pub fn bundle(main_path: &Path, crate_name: &str) -> Result<String, String> {
+    let root_dir = main_path.parent().unwrap_or(Path::new("."));
+
+    let main_source = read_source(main_path)?;
+    let lib_source = read_source(&root_dir.join("lib.rs"))?;
+    let lib_modules = module_declarations(&lib_source);
+
+    let mut used_modules = BTreeSet::new();
+    let mut module_sources = Vec::new();
+    let mut pending = referenced_modules(&main_source, crate_name);
+
+    while let Some(module) = pending.pop_first() {
+        if !lib_modules.contains(&module) || !used_modules.insert(module.clone()) {
+            continue;
+        }
+
+        let source = read_source(&root_dir.join(format!("{}.rs", module)))?;
+        pending.extend(referenced_modules(&source, "crate"));
+        module_sources.push((module, source));
+    }
+
+    let mut bundled = format!("// {}\n", main_path.file_name().unwrap_or_default().to_string_lossy());
+    bundled.push_str(&main_source.replace(&format!("{}::", crate_name), "crate::"));
+    bundled.push('\n');
+
+    for module in lib_modules.iter().filter(|module| used_modules.contains(*module)) {
+        let (_, source) = module_sources.iter().find(|(name, _)| name == module).unwrap();
+
+        bundled.push_str(&format!("// {}.rs\n", module));
+        bundled.push_str(&format!("pub mod {} {{\n", module));
+        bundled.push_str(&expand_modules(source, &root_dir.join(module))?);
+        bundled.push_str("}\n");
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    bundled.push_str(&format!("// bundled at unix time {}\n", timestamp));
+
+    let char_count = bundled.chars().count();
+
+    if char_count > MAX_SUBMISSION_CHARS {
+        return Err(format!("bundle has {} characters, more than the {} CodinGame accepts", char_count, MAX_SUBMISSION_CHARS));
+    }
+
+    Ok(bundled)
+}
+
+/// Reads a source file without comments and without the items left out of the submission.
+fn read_source(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|source| compact_whitespace(&strip_gated_items(&strip_comments(&source))))
+        .map_err(|error| format!("failed to read {}: {}", path.display(), error))
+}
+
+/**
+ * Removes comments, doc comments, indentation, trailing whitespace and blank lines. String and
+ * character literals are kept as they are, except for line breaks within strings, which are
+ * written as `\n` escapes so the lines around them can be trimmed.
+ **/
+pub fn strip_comments(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut code = String::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let next = chars.get(index + 1).copied();
+        let previous_is_ident = index > 0 && (chars[index - 1].is_alphanumeric() || chars[index - 1] == '_');
+
+        match (chars[index], next) {
+            ('/', Some('/')) => {
+                while index < chars.len() && chars[index] != '\n' {
+                    index += 1;
+                }
+            },
+            ('/', Some('*')) => {
+                let mut depth = 0;
+
+                while index < chars.len() {
+                    if chars[index] == '/' && chars.get(index + 1) == Some(&'*') {
+                        depth += 1;
+                        index += 2;
+                    } else if chars[index] == '*' && chars.get(index + 1) == Some(&'/') {
+                        depth -= 1;
+                        index += 2;
+
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        index += 1;
+                    }
+                }
+            },
+            ('r', Some('"' | '#')) if !previous_is_ident && is_raw_string(&chars[index + 1..]) => {
+                let hashes = chars[index + 1..].iter().take_while(|c| **c == '#').count();
+                let closing: Vec<char> = std::iter::once('"').chain(std::iter::repeat_n('#', hashes)).collect();
+                let start = index;
+
+                index += 2 + hashes;
+                while index < chars.len() && !chars[index..].starts_with(&closing) {
+                    index += 1;
+                }
+                index = (index + closing.len()).min(chars.len());
+                code.extend(&chars[start..index]);
+            },
+            ('"', _) => {
+                code.push('"');
+                index += 1;
+
+                while index < chars.len() && chars[index] != '"' {
+                    match chars[index] {
+                        '\\' => {
+                            code.extend(&chars[index..(index + 2).min(chars.len())]);
+                            index += 1;
+                        },
+                        '\n' => code.push_str("\\n"),
+                        c => code.push(c),
+                    }
+                    index += 1;
+                }
+
+                code.push('"');
+                index += 1;
+            },
+            ('\'', Some('\\')) => {
+                let start = index;
+
+                index += 3;
+                while index < chars.len() && chars[index] != '\'' {
+                    index += 1;
+                }
+                index = (index + 1).min(chars.len());
+                code.extend(&chars[start..index]);
+            },
+            ('\'', Some(_)) if chars.get(index + 2) == Some(&'\'') => {
+                code.extend(&chars[index..index + 3]);
+                index += 3;
+            },
+            (c, _) => {
+                code.push(c);
+                index += 1;
+            },
+        }
+    }
+
+    code.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .fold(String::new(), |mut stripped, line| {
+            stripped.push_str(line);
+            stripped.push('\n');
+            stripped
+        })
+}
+
+/// Kinds of characters telling whether whitespace between them is needed to separate tokens.
+#[derive(PartialEq)]
+enum CharClass {
+    Word,
+    Delimiter,
+    Operator,
+}
+
+impl CharClass {
+    fn of(c: char) -> CharClass {
+        match c {
+            c if c.is_alphanumeric() || c == '_' || c == '"' || c == '\'' => CharClass::Word,
+            '(' | ')' | '[' | ']' | '{' | '}' | ',' | ';' => CharClass::Delimiter,
+            _ => CharClass::Operator,
+        }
+    }
+}
+
+/**
+ * Removes the spaces within lines that do not separate tokens. A space is kept between two word
+ * characters, as in `let x`, and between two operator characters, as in `> =`, everything else
+ * like `x = 1` or `f(a, b)` is written without it. Expects code without comments and without line
+ * breaks within literals, as left by `strip_comments`; literals are kept as they are.
+ **/
+pub fn compact_whitespace(source: &str) -> String {
+    let mut compacted = String::new();
+
+    for line in source.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut index = 0;
+
+        while index < chars.len() {
+            let previous_is_ident = index > 0 && (chars[index - 1].is_alphanumeric() || chars[index - 1] == '_');
+            let literal_len = match (chars[index], chars.get(index + 1).copied()) {
+                ('r', Some('"' | '#')) if !previous_is_ident && is_raw_string(&chars[index + 1..]) => {
+                    let hashes = chars[index + 1..].iter().take_while(|c| **c == '#').count();
+                    let closing: Vec<char> = std::iter::once('"').chain(std::iter::repeat_n('#', hashes)).collect();
+
+                    (index + 2 + hashes..=chars.len() - closing.len())
+                        .find(|end| chars[*end..].starts_with(&closing))
+                        .map_or(chars.len() - index, |end| end + closing.len() - index)
+                },
+                ('"', _) => {
+                    let mut end = index + 1;
+
+                    while end < chars.len() && chars[end] != '"' {
+                        end += if chars[end] == '\\' { 2 } else { 1 };
+                    }
+                    (end + 1).min(chars.len()) - index
+                },
+                ('\'', Some('\\')) => chars[index + 3..].iter().position(|c| *c == '\'').map_or(chars.len() - index, |end| end + 4),
+                ('\'', Some(_)) if chars.get(index + 2) == Some(&'\'') => 3,
+                (' ' | '\t', _) => {
+                    let next = chars[index..].iter().position(|c| !c.is_whitespace()).map(|offset| chars[index + offset]);
+                    let previous = compacted.chars().next_back();
+
+                    if let (Some(previous), Some(next)) = (previous, next) {
+                        let class = CharClass::of(previous);
+
+                        if class != CharClass::Delimiter && class == CharClass::of(next) {
+                            compacted.push(' ');
+                        }
+                    }
+
+                    index += chars[index..].iter().take_while(|c| c.is_whitespace()).count();
+                    continue;
+                },
+                _ => 1,
+            };
+
+            compacted.extend(&chars[index..index + literal_len]);
+            index += literal_len;
+        }
+
+        compacted.push('\n');
+    }
+
+    compacted
+}
+
+/// Whether the characters following an `r` open a raw string rather than a raw identifier.
+fn is_raw_string(chars: &[char]) -> bool {
+    chars.iter().find(|c| **c != '#') == Some(&'"')
+}
+
+/// Whether the attribute line leaves the next item out of the submission.
+fn is_gate(line: &str) -> bool {
+    line == "#[cfg(test)]" || line.starts_with("#[cfg(feature")
+}
+
+/**
+ * Removes the items gated by `#[cfg(test)]` or `#[cfg(feature = ..)]`. Expects code without
+ * comments and indentation. An item ends with the first line closing all its brackets that ends in
+ * `;`, `,` or `}`, which covers declarations, statements, match arms and items with a body.
+ **/
+fn strip_gated_items(source: &str) -> String {
+    let mut stripped = String::new();
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        if !is_gate(line) {
+            stripped.push_str(line);
+            stripped.push('\n');
+            continue;
+        }
+
+        let mut depth = 0;
+
+        for line in lines.by_ref() {
+            if is_gate(line) {
+                continue;
+            }
+
+            for c in line.chars() {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {},
+                }
+            }
+
+            if depth <= 0 && line.ends_with([';', ',', '}']) {
+                break;
+            }
+        }
+    }
+
+    stripped
+}
+
+/// Parses a `mod foo;` declaration line, returning the module name.
+fn parse_module_declaration(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+
+    line.strip_prefix("mod ")?
+        .strip_suffix(';')
+        .map(str::trim)
+}
+
+/// Lists the modules declared in a file.
+fn module_declarations(source: &str) -> Vec<String> {
+    source.lines()
+        .filter_map(parse_module_declaration)
+        .map(String::from)
+        .collect()
+}
+
+/// Replaces the `mod foo;` declarations of a module with inline modules, recursively.
+fn expand_modules(source: &str, dir: &Path) -> Result<String, String> {
+    let mut expanded = String::new();
+
+    for line in source.lines() {
+        match parse_module_declaration(line) {
+            Some(module) => {
+                let module_source = read_source(&dir.join(format!("{}.rs", module)))?;
+                let visibility = if line.starts_with("pub ") { "pub " } else { "" };
+
+                expanded.push_str(&format!("{}mod {} {{\n", visibility, module));
+                expanded.push_str(&expand_modules(&module_source, &dir.join(module))?);
+                expanded.push_str("}\n");
+            },
+            None => {
+                expanded.push_str(line);
+                expanded.push('\n');
+            },
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Collects the first path segment after every `prefix::`, including `prefix::{a::X, b::Y}` groups.
+fn referenced_modules(source: &str, prefix: &str) -> BTreeSet<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let first_ident = |segment: &str| segment.trim().chars().take_while(|c| is_ident(*c)).collect::<String>();
+    let pattern = format!("{}::", prefix);
+    let mut segments = Vec::new();
+
+    for (index, _) in source.match_indices(&pattern) {
+        if source[..index].chars().next_back().is_some_and(is_ident) {
+            continue;
+        }
+
+        let rest = &source[index + pattern.len()..];
+
+        match rest.strip_prefix('{') {
+            Some(group) => {
+                let mut depth = 0;
+                let mut segment = String::new();
+
+                for c in group.chars() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' if depth == 0 => break,
+                        '}' => depth -= 1,
+                        ',' if depth == 0 => {
+                            segments.push(first_ident(&segment));
+                            segment.clear();
+                            continue;
+                        },
+                        _ => {},
+                    }
+                    segment.push(c);
+                }
+
+                segments.push(first_ident(&segment));
+            },
+            None => segments.push(first_ident(rest)),
+        }
+    }
+
+    segments.into_iter()
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
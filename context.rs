@@ -1,13 +1,37 @@
-struct Context {
-    sites: Vec<Site>,
-    units: Vec<Unit>,
-    gold: i32,
-    start_position: Vector2,
-    touched_site_id: i32,
+use crate::command::BuildType;
+use crate::economy::GoldEstimate;
+use crate::history::{History, TurnSnapshot};
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::site::{BarracksType, Site, Structure};
+use crate::symmetry::{mirror, Symmetry};
+use crate::unit::{Unit, UnitType};
+
+// assumed for sites whose reserves and rate were never seen, halfway between the extremes
+const UNKNOWN_SITE_GOLD: i32 = 250;
+const UNKNOWN_MAX_MINING_RATE: i32 = 2;
+
+pub struct Context {
+    pub sites: Vec<Site>,
+    pub units: Vec<Unit>,
+    pub gold: i32,
+    pub start_position: Vector2,
+    pub enemy_start_position: Vector2,
+    pub touched_site_id: i32,
+    /// inputs of the previous turns
+    pub history: History,
+    pub enemy_gold: GoldEstimate,
+    pub symmetry: Symmetry,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new()
+    }
 }
 
 impl Context {
-    fn new() -> Context {
+    pub fn new() -> Context {
         Context {
             sites: Vec::new(),
             units: Vec::new(),
@@ -16,29 +40,54 @@ impl Context {
                 x: 0.0,
                 y: 0.0,
             },
+            enemy_start_position: Vector2 {
+                x: 0.0,
+                y: 0.0,
+            },
             touched_site_id: -1,
+            history: History::new(),
+            enemy_gold: GoldEstimate::new(),
+            symmetry: Symmetry::new(&[]),
         }
     }
 
-    fn site_by_id(&self, site_id: i32) -> &Site {
+    /// Adds the input of the current turn to the history.
+    pub fn record_turn(&mut self) {
+        self.history.record(TurnSnapshot {
+            gold: self.gold,
+            sites: self.sites.clone(),
+            units: self.units.clone(),
+        });
+
+        let mut enemy_gold = self.enemy_gold;
+        enemy_gold.update(self);
+        self.enemy_gold = enemy_gold;
+    }
+
+    /// Turns until the enemy can afford training at the barracks type at the earliest.
+    pub fn turns_until_enemy_affords(&self, barracks_type: BarracksType) -> Option<usize> {
+        self.enemy_gold.turns_until_affordable(barracks_type.cost_to_train(), self.history.income(Owner::Enemy, |site| self.estimated_mining_rate(site)))
+    }
+
+    pub fn site_by_id(&self, site_id: i32) -> &Site {
         self.sites.iter()
             .find(|site| site.id == site_id)
             .unwrap()
     }
 
-    fn site_by_id_mut(&mut self, site_id: i32) -> &mut Site {
+    pub fn site_by_id_mut(&mut self, site_id: i32) -> &mut Site {
         self.sites.iter_mut()
             .find(|site| site.id == site_id)
             .unwrap()
     }
 
-    fn friendly_queen(&self) -> &Unit {
+    pub fn friendly_queen(&self) -> &Unit {
         self.units.iter()
             .find(|unit| unit.unit_type == UnitType::Queen && unit.owner == Owner::Friendly)
             .unwrap()
     }
 
-    fn site_count(&self, build_type: BuildType, owner: Owner) -> usize {
+    pub fn site_count(&self, build_type: BuildType, owner: Owner) -> usize {
         self.sites.iter()
             .filter(|site| {
                 match &site.structure {
@@ -58,13 +107,13 @@ impl Context {
             .count()
     }
 
-    fn unit_count(&self, unit_type: UnitType, owner: Owner) -> usize {
+    pub fn unit_count(&self, unit_type: UnitType, owner: Owner) -> usize {
         self.units.iter()
             .filter(|unit| unit.unit_type == unit_type && unit.owner == owner)
             .count()
     }
 
-    fn closest_unit_dist(&self, unit_type: UnitType, owner: Owner) -> Option<i32> {
+    pub fn closest_unit_dist(&self, unit_type: UnitType, owner: Owner) -> Option<i32> {
         let reference_point = self.friendly_queen().position;
 
         self.units.iter()
@@ -73,11 +122,92 @@ impl Context {
             .min()
     }
 
-    fn closest_site(&self, predicate: impl Fn(&Site) -> bool) -> Option<&Site> {
+    pub fn closest_site(&self, predicate: impl Fn(&Site) -> bool) -> Option<&Site> {
         let reference_point = self.friendly_queen().position;
 
         self.sites.iter()
             .filter(|site| predicate(site))
             .min_by_key(|site| (site.position - reference_point).len() as i32)
     }
+
+    /// Takes the queen positions of the first turn as start positions.
+    pub fn detect_start_positions(&mut self) {
+        self.start_position = self.friendly_queen().position;
+        self.enemy_start_position = self.units.iter()
+            .find(|unit| unit.unit_type == UnitType::Queen && unit.owner == Owner::Enemy)
+            .map_or_else(|| mirror(self.start_position), |queen| queen.position);
+    }
+
+    /// Site placed point-symmetrically to the given one, see `Symmetry`.
+    pub fn mirrored_site(&self, site: &Site) -> Option<&Site> {
+        let mirror_id = self.symmetry.mirror_id(site.id)?;
+        self.sites.iter().find(|other| other.id == mirror_id)
+    }
+
+    /**
+     * Signed distance of the position to the border between the territories, the line of points
+     * equally far from both start positions. Positive on our side of the map.
+     **/
+    pub fn border_distance(&self, position: Vector2) -> f64 {
+        let to_start = (position - self.start_position).len();
+        let to_enemy_start = (position - self.enemy_start_position).len();
+        let start_distance = (self.enemy_start_position - self.start_position).len();
+
+        if start_distance <= 0.0 {
+            return 0.0;
+        }
+
+        (to_enemy_start * to_enemy_start - to_start * to_start) / (2.0 * start_distance)
+    }
+
+    pub fn is_friendly_territory(&self, position: Vector2) -> bool {
+        self.border_distance(position) > 0.0
+    }
+
+    /**
+     * Gold left on the site as far as known: the last value seen, otherwise the value of the
+     * mirrored site, which started with the same reserves, otherwise a prior. The gold of mines out
+     * of sight is already reduced by their estimated rate every turn while parsing the input.
+     **/
+    pub fn estimated_gold(&self, site: &Site) -> i32 {
+        let seen_gold = |site: &Site| if site.gold >= 0 { Some(site.gold) } else { None };
+
+        seen_gold(site)
+            .or_else(|| self.mirrored_site(site).and_then(seen_gold))
+            .unwrap_or(UNKNOWN_SITE_GOLD)
+    }
+
+    /// Maximum mining rate of the site as far as known, see `estimated_gold`.
+    pub fn estimated_max_mining_rate(&self, site: &Site) -> i32 {
+        if site.max_mining_rate >= 0 {
+            return site.max_mining_rate;
+        }
+
+        match self.mirrored_site(site) {
+            Some(mirrored) if mirrored.max_mining_rate >= 0 => mirrored.max_mining_rate,
+            _ => UNKNOWN_MAX_MINING_RATE,
+        }
+    }
+
+    /**
+     * Mining rate of the mine on the site. The input leaves out the rate of enemy mines, for which
+     * the maximum rate is assumed, as the enemy usually upgrades its mines right away.
+     **/
+    pub fn estimated_mining_rate(&self, site: &Site) -> i32 {
+        match site.mine().mining_rate {
+            rate if rate >= 0 => rate,
+            _ => self.estimated_max_mining_rate(site),
+        }
+    }
+
+    /// Number of towers of the owner having the position within their attack radius.
+    pub fn towers_in_range(&self, position: Vector2, owner: Owner) -> usize {
+        self.sites.iter()
+            .filter(|site| {
+                site.is_tower()
+                    && site.tower().owner == owner
+                    && (site.position - position).len() < site.tower().attack_radius as f64
+            })
+            .count()
+    }
 }
@@ -0,0 +1,167 @@
+use crate::command::BuildType;
+use crate::context::Context;
+use crate::history::EventKind;
+use crate::owner::Owner;
+use crate::site::{Site, Structure};
+
+/// Gold both players start with.
+pub const STARTING_GOLD: i32 = 100;
+
+/// Turns after which the game ends.
+pub const MAX_TURNS: usize = 200;
+
+/// Value of gold mined a turn later relative to now.
+const GOLD_DISCOUNT: f64 = 0.98;
+
+/// Mine upgrade level worth reaching on a site.
+#[derive(Copy, Clone)]
+pub struct MinePlan {
+    pub site_id: i32,
+    pub rate: i32,
+    /// gold mined until the end of the game minus the gold value of the queen turns spent
+    pub value: f64,
+}
+
+/**
+ * Bounds of the enemy gold, which the protocol does not reveal. Barracks starting to train are
+ * visible, but the mining rates of enemy mines are not, so the bounds drift apart by the upgrades
+ * an enemy mine may have had. A lost enemy mine adds to the drift, as its remaining gold is unknown
+ * and it may have been exhausted or destroyed.
+ **/
+#[derive(Copy, Clone)]
+pub struct GoldEstimate {
+    pub lower: i32,
+    pub upper: i32,
+}
+
+impl GoldEstimate {
+    pub fn new() -> GoldEstimate {
+        GoldEstimate {
+            lower: STARTING_GOLD,
+            upper: STARTING_GOLD,
+        }
+    }
+
+    /// Accounts for the turn last recorded in the history.
+    pub fn update(&mut self, context: &Context) {
+        let history = &context.history;
+        let turn_count = history.turn_count();
+
+        if turn_count < 2 {
+            return;
+        }
+
+        let previous = &history.snapshots[turn_count - 2];
+        let latest = &history.snapshots[turn_count - 1];
+
+        // mines still standing produced their full rate, otherwise they would be exhausted
+        // the rate of enemy mines is hidden, they were built with a rate of 1 and may have been upgraded
+        let enemy_mines = || latest.sites.iter().filter(|site| site.is_mine() && site.mine().owner == Owner::Enemy);
+        let lower_income: i32 = enemy_mines()
+            .map(|site| if site.mine().mining_rate >= 0 { site.mine().mining_rate } else { 1 })
+            .sum();
+        let upper_income: i32 = enemy_mines()
+            .map(|site| context.estimated_mining_rate(site))
+            .sum();
+
+        let mut lost_income = 0;
+        let mut spent = 0;
+
+        for event in history.events_since(turn_count - 1).filter(|event| event.owner == Owner::Enemy) {
+            match event.kind {
+                EventKind::StructureLost(BuildType::Mine) => {
+                    if let Some(site) = previous.sites.iter().find(|site| site.id == event.site_id) {
+                        lost_income += context.estimated_mining_rate(site);
+                    }
+                },
+                EventKind::TrainingStarted(barracks_type) => spent += barracks_type.cost_to_train(),
+                _ => {},
+            }
+        }
+
+        // training proves the enemy had the gold
+        self.lower = self.lower.max(spent) + lower_income - spent;
+        self.upper = (self.upper + upper_income + lost_income - spent).max(self.lower);
+    }
+
+    /// Turns until the enemy can have the given amount of gold at the earliest, None without income.
+    pub fn turns_until_affordable(&self, cost: i32, income: i32) -> Option<usize> {
+        if self.upper >= cost {
+            return Some(0);
+        }
+
+        if income <= 0 {
+            return None;
+        }
+
+        Some(((cost - self.upper + income - 1) / income) as usize)
+    }
+}
+
+impl Default for GoldEstimate {
+    fn default() -> Self {
+        GoldEstimate::new()
+    }
+}
+
+/// Gold a mine on the site yields in the remaining turns when the queen arrives after the
+/// travel turns and upgrades it by one level per turn up to the rate. Gold mined later is
+/// discounted, it buys units later.
+pub fn mine_yield(context: &Context, site: &Site, rate: i32, travel_turns: usize, remaining_turns: usize) -> f64 {
+    let reserves = context.estimated_gold(site);
+    let mut current_rate = match &site.structure {
+        Structure::Mine(mine) if mine.owner == Owner::Friendly => mine.mining_rate,
+        _ => 0,
+    };
+
+    let mut mined = 0;
+    let mut value = 0.0;
+
+    for turn in travel_turns..remaining_turns {
+        if mined >= reserves {
+            break;
+        }
+
+        current_rate = (current_rate + 1).min(rate).max(current_rate);
+        let income = current_rate.min(reserves - mined);
+
+        mined += income;
+        value += income as f64 * GOLD_DISCOUNT.powi(turn as i32);
+    }
+
+    value
+}
+
+/**
+ * Picks the upgrade level of a mine on the site maximizing the discounted gold mined until the
+ * end of the game minus the queen turns spent walking and upgrading, each valued at
+ * `queen_turn_value`. Upgrades pay off less the sooner the reserves run out.
+ **/
+pub fn plan_mine(
+    context: &Context,
+    site: &Site,
+    travel_turns: usize,
+    remaining_turns: usize,
+    queen_turn_value: f64,
+) -> MinePlan {
+    let max_rate = context.estimated_max_mining_rate(site);
+    let current_rate = match &site.structure {
+        Structure::Mine(mine) if mine.owner == Owner::Friendly => mine.mining_rate,
+        _ => 0,
+    };
+
+    let mut best = MinePlan { site_id: site.id, rate: current_rate, value: f64::MIN };
+
+    for rate in current_rate.max(1)..=max_rate {
+        let queen_turns = travel_turns + (rate - current_rate) as usize;
+        let gained = mine_yield(context, site, rate, travel_turns, remaining_turns)
+            - mine_yield(context, site, current_rate, travel_turns, remaining_turns);
+        let value = gained - queen_turns as f64 * queen_turn_value;
+
+        if value > best.value {
+            best = MinePlan { site_id: site.id, rate, value };
+        }
+    }
+
+    best
+}
@@ -0,0 +1,683 @@
+use crate::command::{BuildCommand, BuildType, QueenCommand, TrainCommand};
+use crate::context::Context;
+use crate::economy::MAX_TURNS;
+#[cfg(feature = "offline")]
+use crate::economy::STARTING_GOLD;
+use crate::owner::Owner;
+use crate::path::{MAP_HEIGHT, MAP_WIDTH};
+use crate::point::Vector2;
+#[cfg(feature = "offline")]
+use crate::random::Random;
+#[cfg(feature = "offline")]
+use crate::site::BarracksType;
+use crate::site::{tower_attack_radius, BarracksStructure, MineStructure, Site, Structure, TowerStructure, TOWER_MELT_RATE};
+#[cfg(feature = "offline")]
+use crate::symmetry::mirror;
+use crate::unit::{Unit, UnitType, ARCHER_DAMAGE, ARCHER_DAMAGE_TO_GIANTS, CONTACT_RANGE, GIANT_BUST_RATE, KNIGHT_DAMAGE};
+
+#[cfg(feature = "offline")]
+const VISION_RANGE: f64 = 300.0;
+const COLLISION_ITERATIONS: usize = 5;
+
+#[cfg(feature = "offline")]
+const SITE_GAP: f64 = 90.0;
+#[cfg(feature = "offline")]
+const SITE_GOLD_INCREASE_DISTANCE: f64 = 500.0;
+
+const TOWER_HP_INITIAL: i32 = 200;
+const TOWER_HP_INCREMENT: i32 = 100;
+const TOWER_HP_MAXIMUM: i32 = 800;
+const TOWER_CREEP_DAMAGE_MIN: i32 = 3;
+const TOWER_QUEEN_DAMAGE_MIN: i32 = 1;
+const TOWER_DAMAGE_CLIMB_DISTANCE: f64 = 200.0;
+
+/// Local referee for Code Royale. Owners are stored from the point of view of
+/// the first player and flipped when rendering the input of the second player.
+#[derive(Clone)]
+pub struct Game {
+    pub sites: Vec<Site>,
+    pub units: Vec<Unit>,
+    pub gold: [i32; 2],
+    pub turn: usize,
+    pub forfeits: [bool; 2],
+}
+
+#[cfg(feature = "offline")]
+pub struct GameResult {
+    pub winner: Option<usize>,
+    pub turns: usize,
+    pub gold: [i32; 2],
+    pub structures: [usize; 2],
+    pub queen_health: [i32; 2],
+}
+
+fn owner_of_player(player: usize) -> Owner {
+    match player {
+        0 => Owner::Friendly,
+        _ => Owner::Enemy,
+    }
+}
+
+pub fn player_of_owner(owner: Owner) -> usize {
+    match owner {
+        Owner::Friendly => 0,
+        Owner::Enemy => 1,
+    }
+}
+
+impl Game {
+    #[cfg(feature = "offline")]
+    pub fn new(seed: u64) -> Game {
+        let mut random = Random::new(seed);
+        let mut sites: Vec<Site> = Vec::new();
+        let center = Vector2 { x: MAP_WIDTH / 2.0, y: MAP_HEIGHT / 2.0 };
+        let pair_count = random.range(9, 12) as usize;
+        let mut attempts = 0;
+
+        while sites.len() < pair_count * 2 && attempts < 10000 {
+            attempts += 1;
+
+            let radius = random.range(60, 90);
+            let position = Vector2 {
+                x: random.range(radius, MAP_WIDTH as i32 - radius) as f64,
+                y: random.range(radius, MAP_HEIGHT as i32 - radius) as f64,
+            };
+            let mirrored = mirror(position);
+
+            let too_close = (mirrored - position).len() < (2 * radius) as f64 + SITE_GAP
+                || sites.iter().any(|site| {
+                    let min_dist = (site.radius + radius) as f64 + SITE_GAP;
+                    (site.position - position).len() < min_dist || (site.position - mirrored).len() < min_dist
+                });
+
+            if too_close {
+                continue;
+            }
+
+            let mut gold = random.range(200, 250);
+            let mut max_mining_rate = random.range(1, 3);
+
+            if (position - center).len() > SITE_GOLD_INCREASE_DISTANCE {
+                gold += 50;
+                max_mining_rate += 1;
+            }
+
+            for position in [position, mirrored] {
+                sites.push(Site {
+                    id: sites.len() as i32,
+                    position,
+                    radius,
+                    gold,
+                    max_mining_rate,
+                    resources_visible: true,
+                    structure: Structure::NoStructure,
+                });
+            }
+        }
+
+        let queen_position = Vector2 {
+            x: random.range(30, 300) as f64,
+            y: random.range(30, MAP_HEIGHT as i32 - 30) as f64,
+        };
+
+        let mut game = Game {
+            sites,
+            units: Vec::new(),
+            gold: [STARTING_GOLD; 2],
+            turn: 0,
+            forfeits: [false; 2],
+        };
+
+        for (player, position) in [(0, queen_position), (1, mirror(queen_position))] {
+            game.units.push(Unit {
+                position,
+                owner: owner_of_player(player),
+                unit_type: UnitType::Queen,
+                health: UnitType::Queen.max_health(),
+            });
+        }
+
+        game.resolve_collisions();
+        game
+    }
+
+    /**
+     * Game continuing from the current turn of the bot, for looking ahead. Resources of sites out
+     * of sight and the rates of enemy mines are estimated, the enemy gold is taken halfway between
+     * its bounds.
+     **/
+    pub fn from_context(context: &Context) -> Game {
+        let sites = context.sites.iter()
+            .map(|site| {
+                let mut structure = site.structure.clone();
+
+                if let Structure::Mine(mine) = &mut structure {
+                    mine.mining_rate = context.estimated_mining_rate(site);
+                }
+
+                Site {
+                    gold: context.estimated_gold(site),
+                    max_mining_rate: context.estimated_max_mining_rate(site),
+                    resources_visible: true,
+                    structure,
+                    ..site.clone()
+                }
+            })
+            .collect();
+
+        Game {
+            sites,
+            units: context.units.clone(),
+            gold: [context.gold, (context.enemy_gold.lower + context.enemy_gold.upper) / 2],
+            turn: context.history.turn_count().saturating_sub(1),
+            forfeits: [false; 2],
+        }
+    }
+
+    #[cfg(feature = "offline")]
+    pub fn init_lines(&self) -> Vec<String> {
+        let mut lines = vec![self.sites.len().to_string()];
+
+        for site in &self.sites {
+            lines.push(format!("{} {} {} {}", site.id, site.position.x as i32, site.position.y as i32, site.radius));
+        }
+
+        lines
+    }
+
+    #[cfg(feature = "offline")]
+    pub fn turn_lines(&self, player: usize) -> Vec<String> {
+        let owner_code = |owner: Owner| if player_of_owner(owner) == player { 0 } else { 1 };
+        // like the official referee, the income of the opponent's mines is hidden
+        let mining_rate = |mine: &MineStructure| if player_of_owner(mine.owner) == player { mine.mining_rate } else { -1 };
+        let queen_position = self.queen(player).position;
+
+        let mut lines = vec![format!("{} {}", self.gold[player], self.touched_site_id(player))];
+
+        for site in &self.sites {
+            let visible = (site.position - queen_position).len() - site.radius as f64 <= VISION_RANGE
+                || (site.is_mine() && player_of_owner(site.mine().owner) == player);
+
+            let (gold, max_mining_rate) = if visible {
+                (site.gold, site.max_mining_rate)
+            } else {
+                (-1, -1)
+            };
+
+            let (structure_type, owner, param_1, param_2) = match &site.structure {
+                Structure::NoStructure => (-1, -1, -1, -1),
+                Structure::Mine(mine) => (0, owner_code(mine.owner), mining_rate(mine), -1),
+                Structure::Tower(tower) => (1, owner_code(tower.owner), tower.health, tower.attack_radius),
+                Structure::Barracks(barracks) => {
+                    let barracks_type = match barracks.barracks_type {
+                        BarracksType::Knight => 0,
+                        BarracksType::Archer => 1,
+                        BarracksType::Giant => 2,
+                    };
+                    (2, owner_code(barracks.owner), barracks.turns_to_train, barracks_type)
+                },
+            };
+
+            lines.push(format!(
+                "{} {} {} {} {} {} {}",
+                site.id, gold, max_mining_rate, structure_type, owner, param_1, param_2
+            ));
+        }
+
+        lines.push(self.units.len().to_string());
+
+        for unit in &self.units {
+            let unit_type = match unit.unit_type {
+                UnitType::Queen => -1,
+                UnitType::Knight => 0,
+                UnitType::Archer => 1,
+                UnitType::Giant => 2,
+            };
+
+            lines.push(format!(
+                "{} {} {} {} {}",
+                unit.position.x as i32, unit.position.y as i32, owner_code(unit.owner), unit_type, unit.health
+            ));
+        }
+
+        lines
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.turn >= MAX_TURNS
+            || self.forfeits.iter().any(|forfeit| *forfeit)
+            || (0..2).any(|player| self.queen(player).health <= 0)
+    }
+
+    #[cfg(feature = "offline")]
+    pub fn result(&self) -> GameResult {
+        let queen_health = [self.queen(0).health.max(0), self.queen(1).health.max(0)];
+
+        let winner = match self.forfeits {
+            [true, true] => None,
+            [true, false] => Some(1),
+            [false, true] => Some(0),
+            [false, false] if queen_health[0] > queen_health[1] => Some(0),
+            [false, false] if queen_health[1] > queen_health[0] => Some(1),
+            _ => None,
+        };
+
+        let structures = [0, 1].map(|player| {
+            self.sites.iter()
+                .filter(|site| site.owner().map(player_of_owner) == Some(player))
+                .count()
+        });
+
+        GameResult {
+            winner,
+            turns: self.turn,
+            gold: self.gold,
+            structures,
+            queen_health,
+        }
+    }
+
+    /// Advances the game by one turn given the two output lines of each player.
+    #[cfg(feature = "offline")]
+    pub fn play_turn(&mut self, outputs: [(&str, &str); 2]) {
+        let mut commands = [None, None];
+
+        for (player, (queen_line, train_line)) in outputs.iter().enumerate() {
+            let queen_command = queen_line.parse::<QueenCommand>();
+            let train_command = train_line.parse::<TrainCommand>();
+
+            match (queen_command, train_command) {
+                (Ok(queen_command), Ok(train_command)) => commands[player] = Some((queen_command, train_command)),
+                _ => self.forfeits[player] = true,
+            }
+        }
+
+        self.play_commands(commands);
+    }
+
+    /// Advances the game by one turn given the commands of each player, None for a player forfeiting.
+    pub fn play_commands(&mut self, commands: [Option<(QueenCommand, TrainCommand)>; 2]) {
+        let mut queen_commands = Vec::new();
+
+        for (player, command) in commands.into_iter().enumerate() {
+            if let Some((queen_command, train_command)) = command {
+                self.train(player, &train_command);
+                queen_commands.push((player, queen_command));
+            }
+        }
+
+        for (player, queen_command) in queen_commands {
+            self.execute_queen_command(player, queen_command);
+        }
+
+        self.move_creeps();
+        self.resolve_collisions();
+        self.fight();
+        self.update_structures();
+        self.age_creeps();
+        self.spawn_creeps();
+
+        for unit in &mut self.units {
+            unit.position = Vector2 {
+                x: unit.position.x.round(),
+                y: unit.position.y.round(),
+            };
+        }
+
+        self.turn += 1;
+    }
+
+    pub fn queen(&self, player: usize) -> &Unit {
+        self.units.iter()
+            .find(|unit| unit.unit_type == UnitType::Queen && player_of_owner(unit.owner) == player)
+            .unwrap()
+    }
+
+    fn queen_index(&self, player: usize) -> usize {
+        self.units.iter()
+            .position(|unit| unit.unit_type == UnitType::Queen && player_of_owner(unit.owner) == player)
+            .unwrap()
+    }
+
+    pub fn touched_site_id(&self, player: usize) -> i32 {
+        let queen = self.queen(player);
+
+        self.sites.iter()
+            .find(|site| {
+                let gap = (site.position - queen.position).len() - site.radius as f64 - queen.unit_type.radius();
+                gap <= CONTACT_RANGE
+            })
+            .map(|site| site.id)
+            .unwrap_or(-1)
+    }
+
+    fn train(&mut self, player: usize, train_command: &TrainCommand) {
+        for site_id in &train_command.site_ids {
+            let gold = self.gold[player];
+            let site = match self.sites.iter_mut().find(|site| site.id == *site_id) {
+                Some(site) => site,
+                None => continue,
+            };
+
+            if let Structure::Barracks(barracks) = &mut site.structure {
+                let cost = barracks.barracks_type.cost_to_train();
+
+                if player_of_owner(barracks.owner) == player && barracks.turns_to_train == 0 && gold >= cost {
+                    barracks.turns_to_train = barracks.barracks_type.turns_to_train();
+                    self.gold[player] -= cost;
+                }
+            }
+        }
+    }
+
+    fn execute_queen_command(&mut self, player: usize, queen_command: QueenCommand) {
+        match queen_command {
+            QueenCommand::Wait => {},
+            QueenCommand::Move(target) => self.move_unit(self.queen_index(player), target, 0.0),
+            QueenCommand::Build(build_command) => {
+                let site = match self.sites.iter().find(|site| site.id == build_command.site_id) {
+                    Some(site) => site,
+                    None => {
+                        self.forfeits[player] = true;
+                        return;
+                    },
+                };
+
+                if self.touched_site_id(player) == site.id {
+                    self.build(player, build_command);
+                } else {
+                    let stop_dist = site.radius as f64 + UnitType::Queen.radius();
+                    self.move_unit(self.queen_index(player), site.position, stop_dist);
+                }
+            },
+        }
+    }
+
+    fn build(&mut self, player: usize, build_command: BuildCommand) {
+        let owner = owner_of_player(player);
+        let site = self.sites.iter_mut()
+            .find(|site| site.id == build_command.site_id)
+            .unwrap();
+
+        match (&mut site.structure, build_command.build_type) {
+            (Structure::Tower(tower), _) if tower.owner != owner => {},
+            (Structure::Tower(tower), BuildType::Tower) => {
+                tower.health = (tower.health + TOWER_HP_INCREMENT).min(TOWER_HP_MAXIMUM);
+                tower.attack_radius = tower_attack_radius(tower.health, site.radius);
+            },
+            (Structure::Mine(mine), BuildType::Mine) if mine.owner == owner => {
+                mine.mining_rate = (mine.mining_rate + 1).min(site.max_mining_rate);
+            },
+            (Structure::Barracks(barracks), BuildType::Barracks(barracks_type))
+                if barracks.owner == owner && barracks.barracks_type == barracks_type => {},
+            (_, BuildType::Mine) => {
+                if site.gold > 0 {
+                    site.structure = Structure::Mine(MineStructure {
+                        owner,
+                        mining_rate: 1,
+                    });
+                }
+            },
+            (_, BuildType::Tower) => {
+                site.structure = Structure::Tower(TowerStructure {
+                    owner,
+                    health: TOWER_HP_INITIAL,
+                    attack_radius: tower_attack_radius(TOWER_HP_INITIAL, site.radius),
+                });
+            },
+            (_, BuildType::Barracks(barracks_type)) => {
+                site.structure = Structure::Barracks(BarracksStructure {
+                    owner,
+                    barracks_type,
+                    turns_to_train: 0,
+                });
+            },
+        }
+    }
+
+    /// Moves a unit toward the target with its speed, stopping `stop_dist` away from it.
+    fn move_unit(&mut self, index: usize, target: Vector2, stop_dist: f64) {
+        let unit = &mut self.units[index];
+        let delta = target - unit.position;
+        let step = (delta.len() - stop_dist).min(unit.unit_type.speed());
+
+        if step > 0.0 {
+            unit.position = unit.position + delta.norm().mul(step);
+        }
+    }
+
+    fn move_creeps(&mut self) {
+        for index in 0..self.units.len() {
+            let unit = &self.units[index];
+            let owner = unit.owner;
+            let position = unit.position;
+            let radius = unit.unit_type.radius();
+
+            let target = match unit.unit_type {
+                UnitType::Queen => None,
+                UnitType::Knight => {
+                    let queen = self.queen(1 - player_of_owner(owner));
+                    Some((queen.position, radius + queen.unit_type.radius()))
+                },
+                UnitType::Giant => {
+                    self.sites.iter()
+                        .filter(|site| site.is_tower() && site.tower().owner != owner)
+                        .min_by_key(|site| (site.position - position).len() as i32)
+                        .map(|site| (site.position, radius + site.radius as f64))
+                },
+                UnitType::Archer => {
+                    let enemy_creep = self.units.iter()
+                        .filter(|other| other.owner != owner && other.unit_type != UnitType::Queen)
+                        .min_by_key(|other| (other.position - position).len() as i32);
+
+                    match enemy_creep {
+                        Some(other) => Some((other.position, UnitType::Archer.attack_range())),
+                        None => {
+                            let queen = self.queen(player_of_owner(owner));
+                            Some((queen.position, radius + queen.unit_type.radius()))
+                        },
+                    }
+                },
+            };
+
+            if let Some((target_position, stop_dist)) = target {
+                self.move_unit(index, target_position, stop_dist);
+            }
+        }
+    }
+
+    fn resolve_collisions(&mut self) {
+        for _ in 0..COLLISION_ITERATIONS {
+            for i in 0..self.units.len() {
+                for j in (i + 1)..self.units.len() {
+                    let (type_i, type_j) = (self.units[i].unit_type, self.units[j].unit_type);
+                    let delta = self.units[j].position - self.units[i].position;
+                    let min_dist = type_i.radius() + type_j.radius();
+                    let dist = delta.len();
+
+                    if dist >= min_dist {
+                        continue;
+                    }
+
+                    let normal = if dist > 0.0 { delta.div(dist) } else { Vector2 { x: 1.0, y: 0.0 } };
+                    let overlap = min_dist - dist;
+                    let total_mass = type_i.mass() + type_j.mass();
+
+                    self.units[i].position = self.units[i].position - normal.mul(overlap * type_j.mass() / total_mass);
+                    self.units[j].position = self.units[j].position + normal.mul(overlap * type_i.mass() / total_mass);
+                }
+            }
+
+            for unit in &mut self.units {
+                for site in &self.sites {
+                    let delta = unit.position - site.position;
+                    let min_dist = site.radius as f64 + unit.unit_type.radius();
+                    let dist = delta.len();
+
+                    if dist < min_dist {
+                        let normal = if dist > 0.0 { delta.div(dist) } else { Vector2 { x: 1.0, y: 0.0 } };
+                        unit.position = site.position + normal.mul(min_dist);
+                    }
+                }
+
+                let radius = unit.unit_type.radius();
+                unit.position.x = unit.position.x.clamp(radius, MAP_WIDTH - radius);
+                unit.position.y = unit.position.y.clamp(radius, MAP_HEIGHT - radius);
+            }
+        }
+    }
+
+    fn fight(&mut self) {
+        let mut damages = vec![0; self.units.len()];
+        let mut tower_damages = vec![0; self.sites.len()];
+
+        for unit in &self.units {
+            let radius = unit.unit_type.radius();
+
+            match unit.unit_type {
+                UnitType::Queen => {},
+                UnitType::Knight => {
+                    let queen_index = self.queen_index(1 - player_of_owner(unit.owner));
+                    let queen = &self.units[queen_index];
+                    let gap = (queen.position - unit.position).len() - radius - queen.unit_type.radius();
+
+                    if gap <= CONTACT_RANGE {
+                        damages[queen_index] += KNIGHT_DAMAGE;
+                    }
+                },
+                UnitType::Giant => {
+                    for (site_index, site) in self.sites.iter().enumerate() {
+                        let gap = (site.position - unit.position).len() - radius - site.radius as f64;
+
+                        if site.is_tower() && site.tower().owner != unit.owner && gap <= CONTACT_RANGE {
+                            tower_damages[site_index] += GIANT_BUST_RATE;
+                        }
+                    }
+                },
+                UnitType::Archer => {
+                    let target = self.units.iter()
+                        .enumerate()
+                        .filter(|(_, other)| other.owner != unit.owner && other.unit_type != UnitType::Queen)
+                        .map(|(other_index, other)| {
+                            let gap = (other.position - unit.position).len() - radius - other.unit_type.radius();
+                            (other_index, other.unit_type, gap)
+                        })
+                        .filter(|(_, _, gap)| *gap <= UnitType::Archer.attack_range())
+                        .min_by_key(|(_, _, gap)| *gap as i32);
+
+                    if let Some((other_index, other_type, _)) = target {
+                        damages[other_index] += match other_type {
+                            UnitType::Giant => ARCHER_DAMAGE_TO_GIANTS,
+                            _ => ARCHER_DAMAGE,
+                        };
+                    }
+                },
+            }
+        }
+
+        for site in &self.sites {
+            if !site.is_tower() {
+                continue;
+            }
+
+            let tower = site.tower();
+            let in_range = |unit: &Unit| (unit.position - site.position).len() <= tower.attack_radius as f64;
+
+            let creep_target = self.units.iter()
+                .enumerate()
+                .filter(|(_, unit)| unit.owner != tower.owner && unit.unit_type != UnitType::Queen && in_range(unit))
+                .min_by_key(|(_, unit)| (unit.position - site.position).len() as i32);
+
+            let (target, damage_min) = match creep_target {
+                Some((index, _)) => (Some(index), TOWER_CREEP_DAMAGE_MIN),
+                None => {
+                    let queen_index = self.queen_index(1 - player_of_owner(tower.owner));
+                    let target = Some(queen_index).filter(|index| in_range(&self.units[*index]));
+                    (target, TOWER_QUEEN_DAMAGE_MIN)
+                },
+            };
+
+            if let Some(index) = target {
+                let dist = (self.units[index].position - site.position).len();
+                let climb = ((tower.attack_radius as f64 - dist) / TOWER_DAMAGE_CLIMB_DISTANCE) as i32;
+                damages[index] += damage_min + climb;
+            }
+        }
+
+        for (unit, damage) in self.units.iter_mut().zip(damages) {
+            unit.health -= damage;
+        }
+
+        for (site, damage) in self.sites.iter_mut().zip(tower_damages) {
+            if let Structure::Tower(tower) = &mut site.structure {
+                tower.health -= damage;
+            }
+        }
+    }
+
+    fn update_structures(&mut self) {
+        for site in &mut self.sites {
+            match &mut site.structure {
+                Structure::Tower(tower) => {
+                    tower.health -= TOWER_MELT_RATE;
+                    tower.attack_radius = tower_attack_radius(tower.health.max(0), site.radius);
+
+                    if tower.health <= 0 {
+                        site.structure = Structure::NoStructure;
+                    }
+                },
+                Structure::Mine(mine) => {
+                    let income = mine.mining_rate.min(site.gold);
+                    self.gold[player_of_owner(mine.owner)] += income;
+                    site.gold -= income;
+
+                    if site.gold <= 0 {
+                        site.structure = Structure::NoStructure;
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn age_creeps(&mut self) {
+        for unit in &mut self.units {
+            if unit.unit_type != UnitType::Queen {
+                unit.health -= 1;
+            }
+        }
+
+        self.units.retain(|unit| unit.unit_type == UnitType::Queen || unit.health > 0);
+    }
+
+    fn spawn_creeps(&mut self) {
+        for site in &mut self.sites {
+            if let Structure::Barracks(barracks) = &mut site.structure {
+                if barracks.turns_to_train == 0 {
+                    continue;
+                }
+
+                barracks.turns_to_train -= 1;
+
+                if barracks.turns_to_train > 0 {
+                    continue;
+                }
+
+                let unit_type = barracks.barracks_type.unit_type();
+
+                for index in 0..barracks.barracks_type.units_per_training() {
+                    let offset = Vector2 { x: index as f64 - 1.5, y: (index % 2) as f64 - 0.5 };
+
+                    self.units.push(Unit {
+                        position: site.position + offset,
+                        owner: barracks.owner,
+                        unit_type,
+                        health: unit_type.max_health(),
+                    });
+                }
+            }
+        }
+
+        self.resolve_collisions();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::command::BuildType;
+use crate::owner::Owner;
+use crate::site::{BarracksType, Site, Structure};
+use crate::unit::{Unit, UnitType};
+
+/// Input of one turn as seen by the bot.
+pub struct TurnSnapshot {
+    pub gold: i32,
+    pub sites: Vec<Site>,
+    pub units: Vec<Unit>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    StructureBuilt(BuildType),
+    StructureLost(BuildType),
+    TrainingStarted(BarracksType),
+    UnitsSpawned(UnitType, usize),
+}
+
+/// Change of a site observed between two consecutive turns.
+#[derive(Copy, Clone)]
+pub struct HistoryEvent {
+    /// turn the change was first seen
+    pub turn: usize,
+    pub site_id: i32,
+    pub owner: Owner,
+    pub kind: EventKind,
+}
+
+/**
+ * Snapshots of the turns played so far and the events derived from consecutive snapshots:
+ * structures built and lost, barracks starting to train and units spawned.
+ **/
+pub struct History {
+    pub snapshots: Vec<TurnSnapshot>,
+    pub events: Vec<HistoryEvent>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            snapshots: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Number of recorded turns, the latest snapshot is of turn `turn_count() - 1`.
+    pub fn turn_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn latest(&self) -> Option<&TurnSnapshot> {
+        self.snapshots.last()
+    }
+
+    /// Adds the snapshot of the next turn and derives the events since the previous one.
+    pub fn record(&mut self, snapshot: TurnSnapshot) {
+        let turn = self.snapshots.len();
+
+        if let Some(previous) = self.snapshots.last() {
+            for site in &snapshot.sites {
+                if let Some(before) = previous.sites.iter().find(|before| before.id == site.id) {
+                    History::derive_site_events(turn, before, site, &mut self.events);
+                }
+            }
+        }
+
+        self.snapshots.push(snapshot);
+    }
+
+    fn derive_site_events(turn: usize, before: &Site, after: &Site, events: &mut Vec<HistoryEvent>) {
+        let event = |owner: Owner, kind: EventKind| HistoryEvent { turn, site_id: after.id, owner, kind };
+        let (before_key, after_key) = (structure_key(&before.structure), structure_key(&after.structure));
+
+        if before_key != after_key {
+            if let Some((build_type, owner)) = before_key {
+                events.push(event(owner, EventKind::StructureLost(build_type)));
+            }
+
+            if let Some((build_type, owner)) = after_key {
+                events.push(event(owner, EventKind::StructureBuilt(build_type)));
+            }
+
+            return;
+        }
+
+        if let (Structure::Barracks(before), Structure::Barracks(after)) = (&before.structure, &after.structure) {
+            let barracks_type = after.barracks_type;
+
+            if before.turns_to_train == 1 {
+                let unit_kind = EventKind::UnitsSpawned(barracks_type.unit_type(), barracks_type.units_per_training());
+                events.push(event(after.owner, unit_kind));
+            }
+
+            // counting down by one per turn unless training started again
+            if after.turns_to_train > 0 && after.turns_to_train != before.turns_to_train - 1 {
+                events.push(event(after.owner, EventKind::TrainingStarted(barracks_type)));
+            }
+        }
+    }
+
+    pub fn events_since(&self, turn: usize) -> impl Iterator<Item = &HistoryEvent> {
+        self.events.iter().filter(move |event| event.turn >= turn)
+    }
+
+    /// Turns since the barracks started its current training, None if it is not training.
+    pub fn training_turns(&self, site_id: i32) -> Option<usize> {
+        let latest = self.latest()?;
+        let site = latest.sites.iter().find(|site| site.id == site_id)?;
+
+        if !site.is_barracks() || site.barracks().turns_to_train == 0 {
+            return None;
+        }
+
+        self.events.iter()
+            .rev()
+            .find(|event| event.site_id == site_id && matches!(event.kind, EventKind::TrainingStarted(_)))
+            .map(|event| self.turn_count() - 1 - event.turn)
+    }
+
+    /**
+     * Gold the owner's mines produce per turn as of the latest snapshot. The mining rate of enemy
+     * mines is not part of the input, it is taken from `mining_rate` for such mines.
+     **/
+    pub fn income(&self, owner: Owner, mining_rate: impl Fn(&Site) -> i32) -> i32 {
+        self.latest().map_or(0, |latest| {
+            latest.sites.iter()
+                .filter(|site| site.is_mine() && site.mine().owner == owner)
+                .map(|site| {
+                    // the remaining gold of enemy mines is only known close to our queen
+                    if site.gold >= 0 { mining_rate(site).min(site.gold) } else { mining_rate(site) }
+                })
+                .sum()
+        })
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
+    }
+}
+
+fn structure_key(structure: &Structure) -> Option<(BuildType, Owner)> {
+    match structure {
+        Structure::NoStructure => None,
+        Structure::Barracks(barracks) => Some((BuildType::Barracks(barracks.barracks_type), barracks.owner)),
+        Structure::Tower(tower) => Some((BuildType::Tower, tower.owner)),
+        Structure::Mine(mine) => Some((BuildType::Mine, mine.owner)),
+    }
+}
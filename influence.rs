@@ -0,0 +1,119 @@
+use crate::context::Context;
+use crate::owner::Owner;
+use crate::path::{MAP_HEIGHT, MAP_WIDTH};
+use crate::point::Vector2;
+use crate::site::Site;
+use crate::unit::UnitType;
+
+/// Side length of the square grid cells.
+const CELL_SIZE: f64 = 40.0;
+
+/// Units influence what they can reach within this many turns.
+const INFLUENCE_TURNS: f64 = 3.0;
+
+/// Influence of a start position, fading out across the map.
+const BASE_STRENGTH: f64 = 1.0;
+const BASE_RADIUS: f64 = 1000.0;
+
+const TOWER_STRENGTH: f64 = 2.0;
+
+/**
+ * Influence of both players over the map: each start position, tower, queen and creep adds
+ * influence fading linearly to zero at its reach. Sites deep in our influence are safe, sites
+ * where both players have influence are contested, they lie on the frontier.
+ **/
+pub struct InfluenceMap {
+    columns: usize,
+    rows: usize,
+    friendly: Vec<f64>,
+    enemy: Vec<f64>,
+}
+
+impl InfluenceMap {
+    pub fn new(context: &Context) -> InfluenceMap {
+        let columns = (MAP_WIDTH / CELL_SIZE).ceil() as usize;
+        let rows = (MAP_HEIGHT / CELL_SIZE).ceil() as usize;
+
+        let mut map = InfluenceMap {
+            columns,
+            rows,
+            friendly: vec![0.0; columns * rows],
+            enemy: vec![0.0; columns * rows],
+        };
+
+        map.add(Owner::Friendly, context.start_position, BASE_STRENGTH, BASE_RADIUS);
+        map.add(Owner::Enemy, context.enemy_start_position, BASE_STRENGTH, BASE_RADIUS);
+
+        for site in context.sites.iter().filter(|site| site.is_tower()) {
+            let tower = site.tower();
+            map.add(tower.owner, site.position, TOWER_STRENGTH, tower.attack_radius as f64);
+        }
+
+        for unit in &context.units {
+            let reach = unit.unit_type.speed() * INFLUENCE_TURNS + unit.unit_type.attack_range();
+            map.add(unit.owner, unit.position, InfluenceMap::unit_strength(unit.unit_type), reach);
+        }
+
+        map
+    }
+
+    fn unit_strength(unit_type: UnitType) -> f64 {
+        match unit_type {
+            UnitType::Queen => 1.0,
+            UnitType::Knight => 1.0,
+            UnitType::Archer => 0.5,
+            UnitType::Giant => 0.5,
+        }
+    }
+
+    fn add(&mut self, owner: Owner, position: Vector2, strength: f64, radius: f64) {
+        if radius <= 0.0 {
+            return;
+        }
+
+        let cells = match owner {
+            Owner::Friendly => &mut self.friendly,
+            Owner::Enemy => &mut self.enemy,
+        };
+
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let center = Vector2 {
+                    x: (column as f64 + 0.5) * CELL_SIZE,
+                    y: (row as f64 + 0.5) * CELL_SIZE,
+                };
+                let dist = (center - position).len();
+
+                if dist < radius {
+                    cells[row * self.columns + column] += strength * (1.0 - dist / radius);
+                }
+            }
+        }
+    }
+
+    fn cell_index(&self, position: Vector2) -> usize {
+        let column = ((position.x / CELL_SIZE) as usize).min(self.columns - 1);
+        let row = ((position.y / CELL_SIZE) as usize).min(self.rows - 1);
+
+        row * self.columns + column
+    }
+
+    pub fn influence(&self, position: Vector2, owner: Owner) -> f64 {
+        let index = self.cell_index(position);
+
+        match owner {
+            Owner::Friendly => self.friendly[index],
+            Owner::Enemy => self.enemy[index],
+        }
+    }
+
+    /// Friendly minus enemy influence at the site, positive if we control it.
+    pub fn safety(&self, site: &Site) -> f64 {
+        self.influence(site.position, Owner::Friendly) - self.influence(site.position, Owner::Enemy)
+    }
+
+    /// Influence both players have at the site, highest on the frontier.
+    pub fn contestedness(&self, site: &Site) -> f64 {
+        self.influence(site.position, Owner::Friendly).min(self.influence(site.position, Owner::Enemy))
+    }
+}
@@ -0,0 +1,34 @@
+#[cfg(feature = "offline")]
+pub mod behavior;
+pub mod bot;
+#[cfg(feature = "offline")]
+pub mod bundler;
+pub mod command;
+pub mod context;
+pub mod economy;
+pub mod engine;
+pub mod history;
+pub mod influence;
+pub mod owner;
+pub mod params;
+pub mod path;
+pub mod plan;
+pub mod point;
+pub mod protocol;
+pub mod random;
+#[cfg(feature = "offline")]
+pub mod referee;
+#[cfg(feature = "offline")]
+pub mod replay;
+pub mod search;
+pub mod site;
+pub mod strategy;
+pub mod symmetry;
+pub mod task;
+pub mod threat;
+pub mod timer;
+pub mod unit;
+
+// the tests use the local referee and tools
+#[cfg(all(test, feature = "offline"))]
+mod tests;
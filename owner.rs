@@ -1,5 +1,5 @@
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum Owner {
+pub enum Owner {
     Friendly,
     Enemy,
 }
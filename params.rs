@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+
+/// Prefix of the environment variables overriding parameters, e.g. `CODE_ROYALE_MIN_MINE_COUNT`.
+const ENV_PREFIX: &str = "CODE_ROYALE_";
+
+/// Tunable thresholds of the strategies. The defaults are the values used for submission.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategyParams {
+    /// mines built before the knight barracks
+    pub min_mine_count: usize,
+    /// mines built in total while no knight is close
+    pub max_mine_count: usize,
+    /// towers built before giant barracks and additional mines
+    pub min_tower_count: usize,
+    /// enemy knights predicted to reach the queen within this many turns prevent building mines
+    pub knight_danger_turns: usize,
+    /// sites further away from the start position are not built on
+    pub build_radius: f64,
+    /// towers are upgraded until their attack radius exceeds this
+    pub tower_radius_goal: i32,
+    /// a tower is built first if knights of the next enemy training can arrive within this many turns
+    pub rush_warning_turns: usize,
+    /// enemy knights within the build radius that are answered with archers
+    pub archer_knight_count: usize,
+    /// towers are topped up before their attack radius melts below this
+    pub tower_upkeep_radius: i32,
+    /// towers further away than this many turns of walking are not topped up
+    pub tower_upkeep_travel_turns: usize,
+    /// gold a turn of the queen is worth when weighing mines against their walking and upgrading
+    pub queen_turn_value: f64,
+    /// gold a unit of safety on the influence map is worth when choosing a mine site
+    pub mine_safety_value: f64,
+    /// gold a unit of contestedness on the influence map is worth when choosing a tower site
+    pub tower_frontier_value: f64,
+    /// barracks and towers planned ahead to visit their sites in the shortest order
+    pub plan_length: usize,
+}
+
+impl Default for StrategyParams {
+    fn default() -> Self {
+        StrategyParams {
+            min_mine_count: 3,
+            max_mine_count: 5,
+            min_tower_count: 2,
+            knight_danger_turns: 3,
+            build_radius: 1000.0,
+            tower_radius_goal: 350,
+            rush_warning_turns: 10,
+            archer_knight_count: 4,
+            tower_upkeep_radius: 250,
+            tower_upkeep_travel_turns: 5,
+            queen_turn_value: 10.0,
+            mine_safety_value: 20.0,
+            tower_frontier_value: 20.0,
+            plan_length: 3,
+        }
+    }
+}
+
+impl StrategyParams {
+    pub const KEYS: [&'static str; 14] = [
+        "min_mine_count",
+        "max_mine_count",
+        "min_tower_count",
+        "knight_danger_turns",
+        "build_radius",
+        "tower_radius_goal",
+        "rush_warning_turns",
+        "archer_knight_count",
+        "tower_upkeep_radius",
+        "tower_upkeep_travel_turns",
+        "queen_turn_value",
+        "mine_safety_value",
+        "tower_frontier_value",
+        "plan_length",
+    ];
+
+    /// Loads the defaults, overridden by the given key=value file and then by environment variables.
+    pub fn load(path: Option<&str>) -> Result<StrategyParams, String> {
+        let mut params = StrategyParams::default();
+
+        if let Some(path) = path {
+            let text = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+            params.apply_key_values(&text)?;
+        }
+
+        for key in StrategyParams::KEYS {
+            if let Ok(value) = env::var(format!("{}{}", ENV_PREFIX, key.to_uppercase())) {
+                params.set_str(key, &value)?;
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Applies `key=value` lines, empty lines and lines starting with `#` are ignored.
+    pub fn apply_key_values(&mut self, text: &str) -> Result<(), String> {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(format!("invalid line: {}", line))?;
+            self.set_str(key.trim(), value.trim())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_key_values(&self) -> String {
+        StrategyParams::KEYS.iter()
+            .map(|key| format!("{}={}\n", key, self.get(key).unwrap()))
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        match key {
+            "min_mine_count" => Some(self.min_mine_count as f64),
+            "max_mine_count" => Some(self.max_mine_count as f64),
+            "min_tower_count" => Some(self.min_tower_count as f64),
+            "knight_danger_turns" => Some(self.knight_danger_turns as f64),
+            "build_radius" => Some(self.build_radius),
+            "tower_radius_goal" => Some(self.tower_radius_goal as f64),
+            "rush_warning_turns" => Some(self.rush_warning_turns as f64),
+            "archer_knight_count" => Some(self.archer_knight_count as f64),
+            "tower_upkeep_radius" => Some(self.tower_upkeep_radius as f64),
+            "tower_upkeep_travel_turns" => Some(self.tower_upkeep_travel_turns as f64),
+            "queen_turn_value" => Some(self.queen_turn_value),
+            "mine_safety_value" => Some(self.mine_safety_value),
+            "tower_frontier_value" => Some(self.tower_frontier_value),
+            "plan_length" => Some(self.plan_length as f64),
+            _ => None,
+        }
+    }
+
+    /// Sets a parameter, integer parameters are rounded and clamped at zero.
+    pub fn set(&mut self, key: &str, value: f64) -> Result<(), String> {
+        let count = value.round().max(0.0) as usize;
+
+        match key {
+            "min_mine_count" => self.min_mine_count = count,
+            "max_mine_count" => self.max_mine_count = count,
+            "min_tower_count" => self.min_tower_count = count,
+            "knight_danger_turns" => self.knight_danger_turns = count,
+            "build_radius" => self.build_radius = value,
+            "tower_radius_goal" => self.tower_radius_goal = value.round() as i32,
+            "rush_warning_turns" => self.rush_warning_turns = count,
+            "archer_knight_count" => self.archer_knight_count = count,
+            "tower_upkeep_radius" => self.tower_upkeep_radius = value.round() as i32,
+            "tower_upkeep_travel_turns" => self.tower_upkeep_travel_turns = count,
+            "queen_turn_value" => self.queen_turn_value = value,
+            "mine_safety_value" => self.mine_safety_value = value,
+            "tower_frontier_value" => self.tower_frontier_value = value,
+            "plan_length" => self.plan_length = count,
+            _ => return Err(format!("unknown parameter: {}", key)),
+        }
+
+        Ok(())
+    }
+
+    fn set_str(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.parse::<f64>().map_err(|_| format!("invalid value for {}: {}", key, value))?;
+        self.set(key, value)
+    }
+}
@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::context::Context;
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::unit::UnitType;
+
+pub const MAP_WIDTH: f64 = 1920.0;
+pub const MAP_HEIGHT: f64 = 1000.0;
+
+/// Number of waypoints placed around every obstacle.
+const WAYPOINTS_PER_OBSTACLE: usize = 12;
+
+#[derive(Copy, Clone)]
+pub struct Circle {
+    pub center: Vector2,
+    pub radius: f64,
+}
+
+impl Circle {
+    fn contains(&self, point: Vector2) -> bool {
+        (point - self.center).len() < self.radius
+    }
+
+    /// Whether the segment from `a` to `b` passes through the inside of the circle.
+    fn intersects_segment(&self, a: Vector2, b: Vector2) -> bool {
+        let ab = b - a;
+        let length_squared = Vector2::dot(ab, ab);
+
+        let t = if length_squared > 0.0 {
+            (Vector2::dot(self.center - a, ab) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        (self.center - (a + ab.mul(t))).len() < self.radius - 1e-6
+    }
+}
+
+struct QueueEntry {
+    cost: f64,
+    node: usize,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed for a min-heap
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/**
+ * Plans queen routes around the sites and the attack radius of enemy towers. Obstacles are
+ * inflated by the queen radius and approximated by polygons of waypoints, the shortest route
+ * is searched over the visibility graph of these waypoints.
+ **/
+pub struct PathPlanner {
+    obstacles: Vec<Circle>,
+}
+
+impl PathPlanner {
+    /// Planner for walking to the given site, which itself is not an obstacle. Use -1 for no site.
+    pub fn new(context: &Context, target_site_id: i32) -> PathPlanner {
+        let queen_radius = UnitType::Queen.radius();
+        let mut obstacles = Vec::new();
+
+        for site in context.sites.iter().filter(|site| site.id != target_site_id) {
+            obstacles.push(Circle {
+                center: site.position,
+                radius: site.radius as f64 + queen_radius,
+            });
+        }
+
+        for site in &context.sites {
+            if site.is_tower() && site.tower().owner == Owner::Enemy {
+                obstacles.push(Circle {
+                    center: site.position,
+                    radius: site.tower().attack_radius as f64 + queen_radius,
+                });
+            }
+        }
+
+        PathPlanner { obstacles }
+    }
+
+    /// Waypoints of the shortest route from start to goal, the goal being the last one.
+    /// Obstacles containing the start or the goal are ignored, the route is straight if there is none.
+    pub fn find_path(&self, start: Vector2, goal: Vector2) -> Vec<Vector2> {
+        let obstacles: Vec<Circle> = self.obstacles.iter()
+            .filter(|obstacle| !obstacle.contains(start) && !obstacle.contains(goal))
+            .copied()
+            .collect();
+
+        let is_visible = |a: Vector2, b: Vector2| !obstacles.iter().any(|obstacle| obstacle.intersects_segment(a, b));
+
+        if is_visible(start, goal) {
+            return vec![goal];
+        }
+
+        let mut nodes = vec![start, goal];
+
+        for obstacle in &obstacles {
+            // place the waypoints such that the polygon edges do not cut the circle
+            let angle_step = std::f64::consts::TAU / WAYPOINTS_PER_OBSTACLE as f64;
+            let radius = obstacle.radius / (angle_step / 2.0).cos() + 1.0;
+
+            for index in 0..WAYPOINTS_PER_OBSTACLE {
+                let angle = index as f64 * angle_step;
+                let node = obstacle.center + Vector2 { x: angle.cos(), y: angle.sin() }.mul(radius);
+
+                let inside_map = node.x >= 0.0 && node.x <= MAP_WIDTH && node.y >= 0.0 && node.y <= MAP_HEIGHT;
+
+                if inside_map && !obstacles.iter().any(|other| other.contains(node)) {
+                    nodes.push(node);
+                }
+            }
+        }
+
+        // dijkstra with edges computed on demand
+        let mut costs = vec![f64::INFINITY; nodes.len()];
+        let mut previous = vec![usize::MAX; nodes.len()];
+        let mut queue = BinaryHeap::new();
+
+        costs[0] = 0.0;
+        queue.push(QueueEntry { cost: 0.0, node: 0 });
+
+        while let Some(QueueEntry { cost, node }) = queue.pop() {
+            if node == 1 {
+                break;
+            }
+
+            if cost > costs[node] {
+                continue;
+            }
+
+            for next in 1..nodes.len() {
+                let next_cost = cost + (nodes[next] - nodes[node]).len();
+
+                if next_cost < costs[next] && is_visible(nodes[node], nodes[next]) {
+                    costs[next] = next_cost;
+                    previous[next] = node;
+                    queue.push(QueueEntry { cost: next_cost, node: next });
+                }
+            }
+        }
+
+        if previous[1] == usize::MAX {
+            return vec![goal];
+        }
+
+        let mut path = Vec::new();
+        let mut node = 1;
+
+        while node != 0 {
+            path.push(nodes[node]);
+            node = previous[node];
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Move target following the route for the given distance, the goal itself once it is on the last leg.
+    pub fn next_target(&self, start: Vector2, goal: Vector2, distance: f64) -> Vector2 {
+        let path = self.find_path(start, goal);
+
+        let mut from = start;
+        let mut remaining = distance;
+
+        for &waypoint in &path[..path.len() - 1] {
+            let leg = (waypoint - from).len();
+
+            if leg >= remaining {
+                return from + (waypoint - from).norm().mul(remaining);
+            }
+
+            from = waypoint;
+            remaining -= leg;
+        }
+
+        goal
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+use crate::context::Context;
+use crate::history::EventKind;
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::site::Site;
+use crate::task::Task;
+use crate::unit::UnitType;
+
+/// Only this many sites closest to the queen are considered for a tour.
+const MAX_TOUR_CANDIDATES: usize = 8;
+
+/**
+ * Tasks planned ahead of the current one. The plan is outdated as soon as something happens the
+ * planning did not account for: the enemy builds, trains or loses a structure, or we lose one.
+ **/
+pub struct Plan {
+    tasks: VecDeque<Box<dyn Task>>,
+    /// turn count of the history when the plan was made
+    planned_turn: usize,
+}
+
+impl Plan {
+    pub fn new(context: &Context, tasks: Vec<Box<dyn Task>>) -> Plan {
+        Plan {
+            tasks: tasks.into(),
+            planned_turn: context.history.turn_count(),
+        }
+    }
+
+    pub fn is_outdated(&self, context: &Context) -> bool {
+        context.history.events_since(self.planned_turn).any(|event| {
+            event.owner == Owner::Enemy || !matches!(event.kind, EventKind::StructureBuilt(_) | EventKind::TrainingStarted(_) | EventKind::UnitsSpawned(..))
+        })
+    }
+
+    /// Next planned task that is not already done, None once the plan is through.
+    pub fn next_task(&mut self, context: &Context) -> Option<Box<dyn Task>> {
+        while let Some(task) = self.tasks.pop_front() {
+            if !task.is_task_done(context) {
+                return Some(task);
+            }
+        }
+
+        None
+    }
+}
+
+/// Distance the queen walks from touching one site until she touches the other.
+fn walk_distance(from: &Site, to: &Site) -> f64 {
+    ((to.position - from.position).len() - (from.radius + to.radius) as f64).max(0.0)
+}
+
+/**
+ * Picks `count` of the sites and the order to visit them in that minimize the distance walked
+ * from the start position, an open travelling salesman tour. All orders of the sites closest to
+ * the start are searched exhaustively, pruning tours longer than the best found so far.
+ * Returns fewer site ids if there are not enough sites.
+ **/
+pub fn plan_tour(start: Vector2, sites: &[&Site], count: usize) -> Vec<i32> {
+    let start_distance = |site: &Site| ((site.position - start).len() - site.radius as f64 - UnitType::Queen.radius()).max(0.0);
+
+    let mut candidates: Vec<&Site> = sites.to_vec();
+    candidates.sort_by(|site1, site2| start_distance(site1).total_cmp(&start_distance(site2)));
+    candidates.truncate(MAX_TOUR_CANDIDATES);
+
+    let count = count.min(candidates.len());
+    let mut best = (f64::MAX, Vec::new());
+    let mut tour = Vec::with_capacity(count);
+
+    search_tour(&candidates, count, &start_distance, 0.0, &mut tour, &mut best);
+
+    best.1.into_iter().map(|index| candidates[index].id).collect()
+}
+
+fn search_tour(
+    candidates: &[&Site],
+    count: usize,
+    start_distance: &dyn Fn(&Site) -> f64,
+    distance: f64,
+    tour: &mut Vec<usize>,
+    best: &mut (f64, Vec<usize>),
+) {
+    if distance >= best.0 {
+        return;
+    }
+
+    if tour.len() == count {
+        *best = (distance, tour.clone());
+        return;
+    }
+
+    for index in 0..candidates.len() {
+        if tour.contains(&index) {
+            continue;
+        }
+
+        let step = match tour.last() {
+            Some(&last) => walk_distance(candidates[last], candidates[index]),
+            None => start_distance(candidates[index]),
+        };
+
+        tour.push(index);
+        search_tour(candidates, count, start_distance, distance + step, tour, best);
+        tour.pop();
+    }
+}
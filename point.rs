@@ -2,49 +2,51 @@ use std::ops::Add;
 use std::ops::Sub;
 
 #[derive(Copy, Clone)]
-struct Vector2 {
-    x: f64,
-    y: f64,
+pub struct Vector2 {
+    pub x: f64,
+    pub y: f64,
 }
 
 impl Vector2 {
-    fn len(self) -> f64 {
+    pub fn len(self) -> f64 {
         (self.x * self.x + self.y * self.y).sqrt()
     }
 
-    fn mul(self, f: f64) -> Vector2 {
+    #[allow(clippy::should_implement_trait)]
+    pub fn mul(self, f: f64) -> Vector2 {
         Self {x: self.x * f, y: self.y * f}
     }
 
-    fn div(self, f: f64) -> Vector2 {
+    #[allow(clippy::should_implement_trait)]
+    pub fn div(self, f: f64) -> Vector2 {
         Self {x: self.x / f, y: self.y / f}
     }
 
-    fn norm(self) -> Vector2 {
+    pub fn norm(self) -> Vector2 {
         self.div(self.len())
     }
 
-    fn dot(v1: Vector2, v2: Vector2) -> f64 {
+    pub fn dot(v1: Vector2, v2: Vector2) -> f64 {
         v1.x * v2.x + v1.y * v2.y
     }
 
-    fn comp(self, other: Vector2) -> f64 {
+    pub fn comp(self, other: Vector2) -> f64 {
         Vector2::dot(self, other) / self.len()
     }
 
-    fn proj(self, other: Vector2) -> Vector2 {
+    pub fn proj(self, other: Vector2) -> Vector2 {
         self.norm().mul(self.comp(other))
     }
 
-    fn angle(v1: Vector2, v2: Vector2) -> f64 {
+    pub fn angle(v1: Vector2, v2: Vector2) -> f64 {
         Vector2::dot(v1.norm(), v2.norm()).acos()
     }
 
-    fn perp_cw(self) -> Vector2 {
+    pub fn perp_cw(self) -> Vector2 {
         Vector2 { x: self.y, y: -self.x }
     }
 
-    fn perp_ccw(self) -> Vector2 {
+    pub fn perp_ccw(self) -> Vector2 {
         Vector2 { x: -self.y, y: self.x }
     }
 }
@@ -0,0 +1,251 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Read};
+
+use crate::context::Context;
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::site::{BarracksStructure, BarracksType, MineStructure, Site, Structure, TowerStructure};
+use crate::symmetry::Symmetry;
+use crate::unit::{Unit, UnitType};
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    UnexpectedEof,
+    BadTokenCount { line: String, expected: usize },
+    InvalidNumber(String),
+    UnknownSite(i32),
+    UnknownOwner(i32),
+    UnknownStructureType(i32),
+    UnknownUnitType(i32),
+    UnknownBarracksParam(i32),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Io(error) => write!(f, "failed to read input: {}", error),
+            ProtocolError::UnexpectedEof => write!(f, "unexpected end of input"),
+            ProtocolError::BadTokenCount { line, expected } => {
+                write!(f, "expected {} tokens in line '{}'", expected, line)
+            },
+            ProtocolError::InvalidNumber(token) => write!(f, "invalid number '{}'", token),
+            ProtocolError::UnknownSite(site_id) => write!(f, "unknown site {}", site_id),
+            ProtocolError::UnknownOwner(owner) => write!(f, "unknown owner {}", owner),
+            ProtocolError::UnknownStructureType(structure_type) => {
+                write!(f, "unknown structure type {}", structure_type)
+            },
+            ProtocolError::UnknownUnitType(unit_type) => write!(f, "unknown unit type {}", unit_type),
+            ProtocolError::UnknownBarracksParam(param) => write!(f, "unknown barracks param {}", param),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+impl From<io::Error> for ProtocolError {
+    fn from(error: io::Error) -> Self {
+        ProtocolError::Io(error)
+    }
+}
+
+/// Input reader keeping a copy of every line read, used to dump the raw input of a turn.
+pub struct RecordingReader<R> {
+    inner: R,
+    recorded: Vec<u8>,
+}
+
+impl<R: BufRead> RecordingReader<R> {
+    pub fn new(inner: R) -> RecordingReader<R> {
+        RecordingReader {
+            inner,
+            recorded: Vec::new(),
+        }
+    }
+
+    pub fn take_lines(&mut self) -> Vec<String> {
+        let lines = String::from_utf8_lossy(&self.recorded)
+            .lines()
+            .map(|line| String::from(line.trim()))
+            .collect();
+        self.recorded.clear();
+        lines
+    }
+}
+
+impl<R: BufRead> Read for RecordingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.recorded.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+}
+
+impl<R: BufRead> BufRead for RecordingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.recorded.extend_from_slice(&buf[..amount.min(buf.len())]);
+        }
+        self.inner.consume(amount);
+    }
+}
+
+/// Reads a line and parses it into exactly `count` numbers.
+fn read_numbers(input: &mut impl BufRead, count: usize) -> Result<Vec<i32>, ProtocolError> {
+    let mut line = String::new();
+
+    if input.read_line(&mut line)? == 0 {
+        return Err(ProtocolError::UnexpectedEof);
+    }
+
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+    if tokens.len() != count {
+        return Err(ProtocolError::BadTokenCount {
+            line: String::from(line.trim()),
+            expected: count,
+        });
+    }
+
+    tokens.iter()
+        .map(|token| token.parse::<i32>().map_err(|_| ProtocolError::InvalidNumber(String::from(*token))))
+        .collect()
+}
+
+fn parse_owner(owner: i32) -> Result<Owner, ProtocolError> {
+    match owner {
+        0 => Ok(Owner::Friendly),
+        1 => Ok(Owner::Enemy),
+        _ => Err(ProtocolError::UnknownOwner(owner)),
+    }
+}
+
+fn parse_structure(structure_type: i32, owner: i32, param_1: i32, param_2: i32) -> Result<Structure, ProtocolError> {
+    Ok(match structure_type {
+        -1 => Structure::NoStructure,
+        0 => Structure::Mine(MineStructure {
+            owner: parse_owner(owner)?,
+            mining_rate: param_1,
+        }),
+        1 => Structure::Tower(TowerStructure {
+            owner: parse_owner(owner)?,
+            health: param_1,
+            attack_radius: param_2,
+        }),
+        2 => Structure::Barracks(BarracksStructure {
+            owner: parse_owner(owner)?,
+            barracks_type: match param_2 {
+                0 => BarracksType::Knight,
+                1 => BarracksType::Archer,
+                2 => BarracksType::Giant,
+                _ => return Err(ProtocolError::UnknownBarracksParam(param_2)),
+            },
+            turns_to_train: param_1,
+        }),
+        _ => return Err(ProtocolError::UnknownStructureType(structure_type)),
+    })
+}
+
+fn parse_unit_type(unit_type: i32) -> Result<UnitType, ProtocolError> {
+    match unit_type {
+        -1 => Ok(UnitType::Queen),
+        0 => Ok(UnitType::Knight),
+        1 => Ok(UnitType::Archer),
+        2 => Ok(UnitType::Giant),
+        _ => Err(ProtocolError::UnknownUnitType(unit_type)),
+    }
+}
+
+/// Parses the initialization block with the number of sites and their positions.
+pub fn parse_init(input: &mut impl BufRead) -> Result<Context, ProtocolError> {
+    let mut context = Context::new();
+    let num_sites = read_numbers(input, 1)?[0];
+
+    for _ in 0..num_sites {
+        let inputs = read_numbers(input, 4)?;
+
+        context.sites.push(Site {
+            id: inputs[0],
+            position: Vector2 {
+                x: inputs[1] as f64,
+                y: inputs[2] as f64,
+            },
+            radius: inputs[3],
+            max_mining_rate: -1,
+            gold: -1,
+            resources_visible: false,
+            structure: Structure::NoStructure,
+        });
+    }
+
+    context.symmetry = Symmetry::new(&context.sites);
+
+    Ok(context)
+}
+
+/// Parses the input of a turn into the context. Returns false if the input ended before the turn.
+pub fn parse_turn(input: &mut impl BufRead, context: &mut Context) -> Result<bool, ProtocolError> {
+    let inputs = match read_numbers(input, 2) {
+        Ok(inputs) => inputs,
+        Err(ProtocolError::UnexpectedEof) => return Ok(false),
+        Err(error) => return Err(error),
+    };
+
+    context.gold = inputs[0];
+    context.touched_site_id = inputs[1]; // -1 if none
+
+    for _ in 0..context.sites.len() {
+        let inputs = read_numbers(input, 7)?;
+        let structure = parse_structure(inputs[3], inputs[4], inputs[5], inputs[6])?;
+
+        let index = context.sites.iter()
+            .position(|site| site.id == inputs[0])
+            .ok_or(ProtocolError::UnknownSite(inputs[0]))?;
+
+        // the rate of enemy mines is hidden as well
+        let mining_rate = match &structure {
+            Structure::Mine(mine) if mine.mining_rate > 0 => mine.mining_rate,
+            Structure::Mine(_) => context.estimated_max_mining_rate(&context.sites[index]),
+            _ => 0,
+        };
+
+        let site = &mut context.sites[index];
+
+        // hidden values are -1, the last ones seen are kept instead
+        site.resources_visible = inputs[1] >= 0;
+
+        if site.resources_visible {
+            site.gold = inputs[1];
+            site.max_mining_rate = inputs[2];
+        } else if site.gold > 0 {
+            site.gold = (site.gold - mining_rate).max(0);
+        }
+
+        site.structure = structure;
+    }
+
+    let num_units = read_numbers(input, 1)?[0];
+    context.units.clear();
+
+    for _ in 0..num_units {
+        let inputs = read_numbers(input, 5)?;
+
+        context.units.push(Unit {
+            position: Vector2 {
+                x: inputs[0] as f64,
+                y: inputs[1] as f64,
+            },
+            owner: parse_owner(inputs[2])?,
+            unit_type: parse_unit_type(inputs[3])?,
+            health: inputs[4],
+        });
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,29 @@
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        // xorshift must not start with a zero state
+        Random {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Random integer in the inclusive range `min..=max`.
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        let span = (max - min + 1) as u64;
+        min + (self.next_u64() % span) as i32
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Instant;
+
+use crate::bot::Bot;
+use crate::engine::{Game, GameResult};
+use crate::params::StrategyParams;
+use crate::protocol::{parse_init, parse_turn};
+use crate::strategy::{strategy_by_name, Strategy};
+use crate::timer::{TurnTimer, FIRST_TURN_TIME, TURN_TIME};
+
+/// A bot taking part in a game, exchanging the same lines as on the CodinGame servers.
+pub trait Player {
+    fn send_lines(&mut self, lines: &[String]) -> io::Result<()>;
+    fn read_line(&mut self) -> io::Result<String>;
+}
+
+/**
+ * Bot executable driven over stdin/stdout like on the CodinGame servers. Its output is read on a
+ * thread, so reading fails with `TimedOut` once the bot takes longer to answer a turn than
+ * CodinGame allows.
+ **/
+pub struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    /// times lines were sent, the first ones being the initialization
+    sends: usize,
+    /// when the answer to the lines sent last is due
+    deadline: Instant,
+}
+
+/// Strategy running in the same process, much faster than spawning executables.
+pub struct StrategyPlayer {
+    strategy: Option<Box<dyn Strategy>>,
+    bot: Option<Bot>,
+    timer: TurnTimer,
+    output: VecDeque<String>,
+}
+
+/// Description of a player which can be created for every game.
+#[derive(Clone)]
+pub enum PlayerSpec {
+    Process(String),
+    Strategy(String, StrategyParams),
+}
+
+impl BotProcess {
+    pub fn spawn(command_line: &str) -> io::Result<BotProcess> {
+        let mut args = command_line.split_whitespace();
+        let program = args.next().unwrap_or_default();
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+
+        // ends when the bot exits or is killed
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(BotProcess { child, stdin, lines, sends: 0, deadline: Instant::now() })
+    }
+}
+
+impl Player for BotProcess {
+    fn send_lines(&mut self, lines: &[String]) -> io::Result<()> {
+        // the initialization is answered together with the first turn
+        let budget = if self.sends <= 1 { FIRST_TURN_TIME } else { TURN_TIME };
+        self.sends += 1;
+        self.deadline = Instant::now() + budget;
+
+        for line in lines {
+            writeln!(self.stdin, "{}", line)?;
+        }
+        self.stdin.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        match self.lines.recv_timeout(self.deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(String::from(line.trim())),
+            Err(RecvTimeoutError::Timeout) => Err(io::Error::new(io::ErrorKind::TimedOut, "bot exceeded the response time")),
+            Err(RecvTimeoutError::Disconnected) => Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl StrategyPlayer {
+    pub fn new(strategy: Box<dyn Strategy>) -> StrategyPlayer {
+        StrategyPlayer {
+            strategy: Some(strategy),
+            bot: None,
+            timer: TurnTimer::new(),
+            output: VecDeque::new(),
+        }
+    }
+}
+
+impl Player for StrategyPlayer {
+    fn send_lines(&mut self, lines: &[String]) -> io::Result<()> {
+        let to_io_error = |error| io::Error::new(io::ErrorKind::InvalidData, format!("{}", error));
+        let mut input = Cursor::new(lines.join("\n"));
+
+        match &mut self.bot {
+            None => {
+                let context = parse_init(&mut input).map_err(to_io_error)?;
+                self.bot = Some(Bot::new(context, self.strategy.take().unwrap()));
+            },
+            Some(bot) => {
+                self.timer.start_turn();
+                parse_turn(&mut input, &mut bot.context).map_err(to_io_error)?;
+                let (queen_command, train_command) = bot.play_turn(&self.timer);
+                self.output.push_back(queen_command.to_string());
+                self.output.push_back(train_command.to_string());
+            },
+        }
+
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        self.output.pop_front().ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+}
+
+impl PlayerSpec {
+    /// Parses `strategy:<name>[:<params file>]` as an in-process strategy, anything else as a command line.
+    pub fn parse(spec: &str) -> Result<PlayerSpec, String> {
+        let Some(strategy_spec) = spec.strip_prefix("strategy:") else {
+            return Ok(PlayerSpec::Process(String::from(spec)));
+        };
+
+        let (name, params_path) = match strategy_spec.split_once(':') {
+            Some((name, params_path)) => (name, Some(params_path)),
+            None => (strategy_spec, None),
+        };
+
+        let params = StrategyParams::load(params_path)?;
+
+        if strategy_by_name(name, params.clone()).is_none() {
+            return Err(format!("unknown strategy: {}", name));
+        }
+
+        Ok(PlayerSpec::Strategy(String::from(name), params))
+    }
+
+    pub fn create(&self) -> io::Result<Box<dyn Player>> {
+        match self {
+            PlayerSpec::Process(command_line) => Ok(Box::new(BotProcess::spawn(command_line)?)),
+            PlayerSpec::Strategy(name, params) => {
+                Ok(Box::new(StrategyPlayer::new(strategy_by_name(name, params.clone()).unwrap())))
+            },
+        }
+    }
+}
+
+/// Plays a full game, the first player being player 0.
+pub fn play_game(players: [&PlayerSpec; 2], seed: u64) -> io::Result<GameResult> {
+    let mut game = Game::new(seed);
+    let mut players = [players[0].create()?, players[1].create()?];
+
+    for player in &mut players {
+        player.send_lines(&game.init_lines())?;
+    }
+
+    while !game.is_over() {
+        let mut outputs = Vec::new();
+
+        for (index, player) in players.iter_mut().enumerate() {
+            // a bot that crashed or timed out leaves empty lines behind, which forfeits the game
+            let _ = player.send_lines(&game.turn_lines(index));
+            let queen_line = player.read_line().unwrap_or_default();
+            let train_line = player.read_line().unwrap_or_default();
+            outputs.push((queen_line, train_line));
+        }
+
+        game.play_turn([
+            (&outputs[0].0, &outputs[0].1),
+            (&outputs[1].0, &outputs[1].1),
+        ]);
+    }
+
+    Ok(game.result())
+}
+
+/**
+ * Plays games on consecutive seeds in parallel. Every seed is played twice with swapped sides,
+ * so both players get the same site layouts and start positions. Returns for every game
+ * whether the sides were swapped together with the result.
+ **/
+pub fn play_series(players: [&PlayerSpec; 2], game_count: usize, first_seed: u64) -> io::Result<Vec<(bool, GameResult)>> {
+    let next_game = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let thread_count = thread::available_parallelism().map_or(1, |count| count.get());
+
+    thread::scope(|scope| {
+        for _ in 0..thread_count {
+            let sender = sender.clone();
+            let next_game = &next_game;
+
+            scope.spawn(move || loop {
+                let game = next_game.fetch_add(1, Ordering::Relaxed);
+
+                if game >= game_count {
+                    break;
+                }
+
+                let seed = first_seed + (game / 2) as u64;
+                let swapped = game % 2 == 1;
+                let sides = if swapped { [players[1], players[0]] } else { players };
+                let result = play_game(sides, seed).map(|result| (swapped, result));
+
+                sender.send(result).unwrap();
+            });
+        }
+    });
+
+    drop(sender);
+    receiver.into_iter().collect()
+}
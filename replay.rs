@@ -0,0 +1,82 @@
+use std::fs;
+use std::io;
+use std::io::{Cursor, Write};
+
+use crate::bot::run_bot;
+use crate::protocol::ProtocolError;
+use crate::strategy::Strategy;
+
+/// Extracts the input lines from a stderr log, skipping any other debug output.
+/// Every turn is dumped as a single line with all input lines terminated by `;`.
+pub fn parse_turn_dump(log: &str) -> Vec<String> {
+    log.lines()
+        .map(|line| line.trim())
+        .filter(|line| line.ends_with(';'))
+        .flat_map(|line| line.split(';').filter(|input_line| !input_line.is_empty()))
+        .map(String::from)
+        .collect()
+}
+
+/// Outcome of replaying a log.
+pub struct ReplaySummary {
+    /// turns the bot decided before the log ended or could not be parsed
+    pub turns: usize,
+    /// turns whose commands differ from the ones played, zero without played commands
+    pub mismatches: usize,
+    /// why the replay stopped early, None if the whole log was replayed
+    pub error: Option<ProtocolError>,
+}
+
+/**
+ * Re-runs the decision logic on a stderr log of a previous game and prints the commands per turn.
+ * If the commands actually played are given (two stdout lines per turn), differences are marked.
+ **/
+pub fn replay(log_path: &str, played_path: Option<&str>, strategy: Box<dyn Strategy>) {
+    let log = fs::read_to_string(log_path).expect("failed to read log");
+    let played = played_path.map(|path| fs::read_to_string(path).expect("failed to read played commands"));
+
+    let summary = replay_log(&log, played.as_deref(), strategy, &mut io::stdout());
+
+    if let Some(error) = &summary.error {
+        eprintln!("replay stopped: {}", error);
+    }
+
+    if played_path.is_some() {
+        println!("{} of {} turns differ", summary.mismatches, summary.turns);
+    }
+}
+
+/// Replays the log text against the played stdout text, printing the commands of each turn to `out`.
+pub fn replay_log(log: &str, played: Option<&str>, strategy: Box<dyn Strategy>, out: &mut impl Write) -> ReplaySummary {
+    let input = parse_turn_dump(log).join("\n");
+    let played: Vec<&str> = played.map_or(Vec::new(), |played| {
+        played.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect()
+    });
+
+    let mut output: Vec<u8> = Vec::new();
+    let error = run_bot(Cursor::new(input), &mut output, strategy, false, None).err();
+
+    let output = String::from_utf8(output).unwrap();
+    let commands = output.lines().collect::<Vec<_>>();
+    let mut mismatches = 0;
+
+    for (turn, turn_commands) in commands.chunks(2).enumerate() {
+        writeln!(out, "turn {}: {}", turn, turn_commands.join(" | ")).unwrap();
+
+        if let Some(played_commands) = played.get(turn * 2..turn * 2 + 2) {
+            if played_commands != turn_commands {
+                writeln!(out, "  played: {}", played_commands.join(" | ")).unwrap();
+                mismatches += 1;
+            }
+        }
+    }
+
+    ReplaySummary {
+        turns: commands.len() / 2,
+        mismatches,
+        error,
+    }
+}
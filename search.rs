@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use crate::command::{BuildCommand, BuildType, QueenCommand, TrainCommand};
+use crate::context::Context;
+use crate::engine::{player_of_owner, Game};
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::random::Random;
+use crate::site::{BarracksType, Site, Structure};
+use crate::task::ARRIVAL_DISTANCE;
+use crate::timer::TurnTimer;
+
+/// Turns simulated by each rollout.
+const ROLLOUT_TURNS: usize = 20;
+
+/// Rollouts run for each candidate action.
+const ROLLOUTS_PER_ACTION: usize = 4;
+
+/// Another round of rollouts is started only while this much time of the turn is left.
+const SEARCH_TIME_RESERVE: Duration = Duration::from_millis(20);
+
+/// Actions of the rollout policy are drawn from this many free sites closest to the queen.
+const POLICY_SITE_COUNT: usize = 4;
+
+/// Structures the rollout policy builds, knight barracks being the only ones trained at.
+const POLICY_BUILD_TYPES: [BuildType; 3] = [BuildType::Mine, BuildType::Tower, BuildType::Barracks(BarracksType::Knight)];
+
+// gold the state at the end of a rollout is worth per unit
+const QUEEN_HEALTH_VALUE: f64 = 20.0;
+const MINING_RATE_VALUE: f64 = 30.0;
+const TOWER_HEALTH_VALUE: f64 = 0.2;
+const BARRACKS_VALUE: f64 = 80.0;
+
+/// What the queen does over several turns, the unit of decision of the search.
+#[derive(Clone)]
+pub enum MacroAction {
+    /// walk to the site and build on it until the structure stands
+    Build(BuildCommand),
+    /// walk to a position, e.g. behind friendly towers
+    Move(Vector2),
+}
+
+/**
+ * Flat Monte Carlo search over queen actions: each candidate is played out by rollouts of the
+ * local referee from the current turn, after which both queens pick random actions near them and
+ * train knights whenever they can. The candidate whose rollouts end in the best material balance
+ * on average is chosen. The rollouts of all candidates share their random seeds, so candidates
+ * are compared under the same course of the game.
+ **/
+pub struct MonteCarloSearch {
+    seed: u64,
+}
+
+impl MacroAction {
+    /// Command carrying out the action this turn.
+    fn command(&self) -> QueenCommand {
+        match self {
+            MacroAction::Build(build_command) => QueenCommand::Build(build_command.clone()),
+            MacroAction::Move(position) => QueenCommand::Move(*position),
+        }
+    }
+
+    /// Whether there is nothing left to do for the owner's queen, the action is done or impossible.
+    fn is_done(&self, game: &Game, owner: Owner) -> bool {
+        match self {
+            MacroAction::Build(build_command) => {
+                let site = game.sites.iter().find(|site| site.id == build_command.site_id).unwrap();
+
+                match (&site.structure, build_command.build_type) {
+                    (Structure::Tower(tower), _) if tower.owner != owner => true,
+                    (Structure::Mine(mine), BuildType::Mine) => mine.owner == owner && mine.mining_rate >= site.max_mining_rate,
+                    (Structure::Tower(_), BuildType::Tower) => true,
+                    (Structure::Barracks(barracks), BuildType::Barracks(barracks_type)) => {
+                        barracks.owner == owner && barracks.barracks_type == barracks_type
+                    },
+                    (_, BuildType::Mine) => site.gold <= 0,
+                    _ => false,
+                }
+            },
+            MacroAction::Move(position) => (game.queen(player_of_owner(owner)).position - *position).len() <= ARRIVAL_DISTANCE,
+        }
+    }
+}
+
+impl MonteCarloSearch {
+    pub fn new(seed: u64) -> MonteCarloSearch {
+        MonteCarloSearch { seed }
+    }
+
+    /**
+     * Candidate with the best average outcome, None if there are no candidates. The candidates take
+     * turns in running a rollout, so the search can stop whenever the time of the turn runs short
+     * and returns the best candidate so far. None if not even one round of rollouts was run.
+     **/
+    pub fn best_action(&self, context: &Context, candidates: &[MacroAction], timer: &TurnTimer) -> Option<MacroAction> {
+        let game = Game::from_context(context);
+        let mut totals = vec![0.0; candidates.len()];
+        let mut rounds = 0;
+
+        while rounds < ROLLOUTS_PER_ACTION && timer.remaining() > SEARCH_TIME_RESERVE {
+            let seed = self.seed.wrapping_add(rounds as u64);
+
+            for (total, action) in totals.iter_mut().zip(candidates) {
+                *total += self.rollout(&game, action, seed);
+            }
+
+            rounds += 1;
+        }
+
+        if rounds == 0 {
+            return None;
+        }
+
+        candidates.iter()
+            .zip(totals)
+            .max_by(|(_, total1), (_, total2)| total1.total_cmp(total2))
+            .map(|(action, _)| action.clone())
+    }
+
+    /// Plays the action out and returns the score of the game at the end of the rollout.
+    fn rollout(&self, game: &Game, action: &MacroAction, seed: u64) -> f64 {
+        let mut game = game.clone();
+        let mut random = Random::new(seed);
+        let mut actions = [action.clone(), random_action(&game, Owner::Enemy, &mut random)];
+
+        for _ in 0..ROLLOUT_TURNS {
+            if game.is_over() {
+                break;
+            }
+
+            for (action, owner) in actions.iter_mut().zip([Owner::Friendly, Owner::Enemy]) {
+                if action.is_done(&game, owner) {
+                    *action = random_action(&game, owner, &mut random);
+                }
+            }
+
+            let commands = [Owner::Friendly, Owner::Enemy].map(|owner| {
+                Some((actions[player_of_owner(owner)].command(), train_command(&game, owner)))
+            });
+
+            game.play_commands(commands);
+        }
+
+        evaluate(&game, Owner::Friendly) - evaluate(&game, Owner::Enemy)
+    }
+}
+
+/// Builds a random structure on one of the free sites closest to the queen, stays if there is none.
+fn random_action(game: &Game, owner: Owner, random: &mut Random) -> MacroAction {
+    let queen_position = game.queen(player_of_owner(owner)).position;
+    let mut sites: Vec<&Site> = game.sites.iter()
+        .filter(|site| matches!(site.structure, Structure::NoStructure))
+        .collect();
+
+    sites.sort_by(|site1, site2| {
+        (site1.position - queen_position).len().total_cmp(&(site2.position - queen_position).len())
+    });
+    sites.truncate(POLICY_SITE_COUNT);
+
+    if sites.is_empty() {
+        return MacroAction::Move(queen_position);
+    }
+
+    let site = sites[random.range(0, sites.len() as i32 - 1) as usize];
+    let build_type = POLICY_BUILD_TYPES[random.range(0, POLICY_BUILD_TYPES.len() as i32 - 1) as usize];
+
+    MacroAction::Build(BuildCommand::for_site(site, build_type))
+}
+
+/// Trains knights at every idle barracks of the owner as long as the gold lasts.
+fn train_command(game: &Game, owner: Owner) -> TrainCommand {
+    let mut gold = game.gold[player_of_owner(owner)];
+    let mut train_sites = Vec::new();
+
+    for site in &game.sites {
+        if let Structure::Barracks(barracks) = &site.structure {
+            let cost = barracks.barracks_type.cost_to_train();
+
+            if barracks.owner == owner && barracks.turns_to_train == 0 && gold >= cost {
+                train_sites.push(site);
+                gold -= cost;
+            }
+        }
+    }
+
+    TrainCommand::for_sites(train_sites)
+}
+
+/// Material of the owner in gold: queen health, gold, mining rates, tower health and barracks.
+fn evaluate(game: &Game, owner: Owner) -> f64 {
+    let gold = game.gold[player_of_owner(owner)];
+    let mut score = game.queen(player_of_owner(owner)).health.max(0) as f64 * QUEEN_HEALTH_VALUE + gold as f64;
+
+    for site in game.sites.iter().filter(|site| site.owner() == Some(owner)) {
+        score += match &site.structure {
+            Structure::Mine(mine) => mine.mining_rate as f64 * MINING_RATE_VALUE,
+            Structure::Tower(tower) => tower.health as f64 * TOWER_HEALTH_VALUE,
+            Structure::Barracks(_) => BARRACKS_VALUE,
+            Structure::NoStructure => 0.0,
+        };
+    }
+
+    score
+}
@@ -0,0 +1,44 @@
+use std::env;
+
+use code_royale::referee::{play_game, PlayerSpec};
+
+/**
+ * Plays a local game between two bots, see `PlayerSpec::parse` for in-process strategies.
+ * Usage: simulate <bot> <bot> [seed]
+ **/
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 3 {
+        eprintln!("usage: {} <bot> <bot> [seed]", args[0]);
+        std::process::exit(2);
+    }
+
+    let seed = match args.get(3) {
+        Some(seed) => seed.parse::<u64>().expect("seed must be a number"),
+        None => 1,
+    };
+
+    let players = [&args[1], &args[2]].map(|spec| {
+        PlayerSpec::parse(spec).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        })
+    });
+
+    let result = play_game([&players[0], &players[1]], seed).expect("failed to run bots");
+
+    let winner = match result.winner {
+        Some(player) => format!("player {}", player),
+        None => String::from("draw"),
+    };
+
+    println!("seed {}: {} after {} turns", seed, winner, result.turns);
+
+    for player in 0..2 {
+        println!(
+            "player {}: queen health {}, gold {}, structures {}",
+            player, result.queen_health[player], result.gold[player], result.structures[player]
+        );
+    }
+}
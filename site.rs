@@ -1,97 +1,147 @@
-#[derive(Copy, Clone, PartialEq, Eq)]
-enum BarracksType {
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::unit::UnitType;
+
+/// Tower health lost per turn.
+pub const TOWER_MELT_RATE: i32 = 4;
+
+/// Area a tower covers per health point in addition to its site.
+const TOWER_COVERAGE_PER_HP: f64 = 1000.0;
+
+/// Attack radius of a tower with the given health on a site of the given radius.
+pub fn tower_attack_radius(health: i32, site_radius: i32) -> i32 {
+    let site_area = std::f64::consts::PI * (site_radius * site_radius) as f64;
+    ((health as f64 * TOWER_COVERAGE_PER_HP + site_area) / std::f64::consts::PI).sqrt() as i32
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BarracksType {
     Knight,
     Archer,
     Giant,
 }
 
-struct BarracksStructure {
-    owner: Owner,
-    barracks_type: BarracksType,
-    turns_to_train: i32,
+#[derive(Clone)]
+pub struct BarracksStructure {
+    pub owner: Owner,
+    pub barracks_type: BarracksType,
+    pub turns_to_train: i32,
 }
 
-struct TowerStructure {
-    owner: Owner,
-    health: i32,
-    attack_radius: i32,
+#[derive(Clone)]
+pub struct TowerStructure {
+    pub owner: Owner,
+    pub health: i32,
+    pub attack_radius: i32,
 }
 
-struct MineStructure {
-    owner: Owner,
-    mining_rate: i32,
+#[derive(Clone)]
+pub struct MineStructure {
+    pub owner: Owner,
+    pub mining_rate: i32,
 }
 
-enum Structure {
+#[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum Structure {
     NoStructure,
     Barracks(BarracksStructure),
     Tower(TowerStructure),
     Mine(MineStructure),
 }
 
-struct Site {
-    id: i32,
-    position: Vector2,
-    radius: i32,
-    gold: i32,
-    max_mining_rate: i32,
-    structure: Structure,
+#[derive(Clone)]
+pub struct Site {
+    pub id: i32,
+    pub position: Vector2,
+    pub radius: i32,
+    pub gold: i32,
+    pub max_mining_rate: i32,
+    /// false if `gold` and `max_mining_rate` are the last values seen, -1 if never seen
+    pub resources_visible: bool,
+    pub structure: Structure,
 }
 
 impl BarracksType {
-    fn cost_to_train(&self) -> i32 {
+    pub fn cost_to_train(&self) -> i32 {
         match self {
             BarracksType::Knight => 80,
             BarracksType::Archer => 100,
             BarracksType::Giant => 140,
         }
     }
+
+    pub fn turns_to_train(&self) -> i32 {
+        match self {
+            BarracksType::Knight => 5,
+            BarracksType::Archer => 8,
+            BarracksType::Giant => 10,
+        }
+    }
+
+    pub fn unit_type(&self) -> UnitType {
+        match self {
+            BarracksType::Knight => UnitType::Knight,
+            BarracksType::Archer => UnitType::Archer,
+            BarracksType::Giant => UnitType::Giant,
+        }
+    }
+
+    pub fn units_per_training(&self) -> usize {
+        match self {
+            BarracksType::Knight => 4,
+            BarracksType::Archer => 2,
+            BarracksType::Giant => 1,
+        }
+    }
 }
 
 impl Site {
-    fn is_mine(&self) -> bool {
-        match &self.structure {
-            Structure::Mine(_) => true,
-            _ => false,
-        }
+    pub fn is_mine(&self) -> bool {
+        matches!(&self.structure, Structure::Mine(_))
     }
 
-    fn mine(&self) -> &MineStructure {
+    pub fn mine(&self) -> &MineStructure {
         match &self.structure {
             Structure::Mine(mine) => mine,
             _ => panic!(),
         }
     }
 
-    fn is_barracks(&self) -> bool {
-        match &self.structure {
-            Structure::Barracks(_) => true,
-            _ => false,
-        }
+    pub fn is_barracks(&self) -> bool {
+        matches!(&self.structure, Structure::Barracks(_))
     }
 
-    fn barracks(&self) -> &BarracksStructure {
+    pub fn barracks(&self) -> &BarracksStructure {
         match &self.structure {
             Structure::Barracks(barracks) => barracks,
             _ => panic!(),
         }
     }
 
-    fn is_tower(&self) -> bool {
-        match &self.structure {
-            Structure::Tower(_) => true,
-            _ => false,
-        }
+    pub fn is_tower(&self) -> bool {
+        matches!(&self.structure, Structure::Tower(_))
     }
 
-    fn tower(&self) -> &TowerStructure {
+    pub fn tower(&self) -> &TowerStructure {
         match &self.structure {
             Structure::Tower(tower) => tower,
             _ => panic!(),
         }
     }
 
-    fn owner(&self) -> Option<Owner> {
+    /// Tower health needed on this site for the given attack radius, the inverse of `tower_attack_radius`.
+    pub fn tower_health_for_radius(&self, attack_radius: i32) -> i32 {
+        let covered_area = std::f64::consts::PI * (attack_radius * attack_radius - self.radius * self.radius) as f64;
+        (covered_area / TOWER_COVERAGE_PER_HP).ceil() as i32
+    }
+
+    /// Turns until the tower melts below the given attack radius, zero if it already is.
+    pub fn tower_turns_above_radius(&self, attack_radius: i32) -> i32 {
+        ((self.tower().health - self.tower_health_for_radius(attack_radius)) / TOWER_MELT_RATE).max(0)
+    }
+
+    pub fn owner(&self) -> Option<Owner> {
         match &self.structure {
             Structure::Mine(mine) => Some(mine.owner),
             Structure::Barracks(barracks) => Some(barracks.owner),
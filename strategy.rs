@@ -0,0 +1,903 @@
+#[cfg(feature = "offline")]
+use std::cell::RefCell;
+#[cfg(feature = "offline")]
+use std::rc::Rc;
+
+#[cfg(feature = "offline")]
+use crate::behavior::{BehaviorTree, Condition, Inverter, Node, Selector, Sequence, TaskAction};
+use crate::command::{BuildCommand, BuildType, TrainCommand};
+use crate::context::Context;
+use crate::economy::{plan_mine, MinePlan, MAX_TURNS};
+use crate::history::EventKind;
+use crate::influence::InfluenceMap;
+use crate::owner::Owner;
+use crate::params::StrategyParams;
+use crate::plan::plan_tour;
+use crate::search::{MacroAction, MonteCarloSearch};
+use crate::site::{BarracksType, Site, Structure};
+#[cfg(feature = "offline")]
+use crate::task::CommandTask;
+use crate::task::{
+    BuildBarracksTask, BuildMineTask, BuildTowerTask, FleeTask, MoveToBuildSiteTask, MoveToPositionTask, PriorityTask, Task, TaskPriority,
+    TowerUpkeepTask, WaitTask,
+};
+use crate::threat::{ThreatPredictor, PREDICTION_TURNS};
+use crate::timer::TurnTimer;
+use crate::unit::UnitType;
+
+/// Towers lost within this many turns are rebuilt as a matter of urgency.
+const TOWER_REBUILD_TURNS: usize = 20;
+
+/// Sites closest to the queen the search strategy considers building on.
+const SEARCH_SITE_COUNT: usize = 5;
+
+/// Decides what the queen builds next and which units are trained.
+pub trait Strategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task>;
+    fn get_next_unit_to_train(&self, context: &Context) -> Option<BarracksType>;
+
+    /// Task interrupting the current one if its priority is higher, checked every turn.
+    fn get_urgent_task(&self, _context: &Context) -> Option<Box<dyn Task>> {
+        None
+    }
+
+    /// Tasks to work through in order, the first one starts right away. Re-planned once they are
+    /// done or the plan is outdated, see `Plan`.
+    fn get_next_plan(&self, context: &Context, _timer: &TurnTimer) -> Vec<Box<dyn Task>> {
+        vec![self.get_next_task(context)]
+    }
+
+    /// Line printed to the debug output after each turn.
+    fn get_debug_info(&self) -> Option<String> {
+        None
+    }
+
+    /// Strategy to continue the game with, checked every turn before selecting tasks.
+    fn get_next_strategy(&self, _context: &Context) -> Option<Box<dyn Strategy>> {
+        None
+    }
+
+    fn get_train_command(&self, context: &Context) -> TrainCommand {
+        let mut gold_available = context.gold;
+        let mut train_sites: Vec<&Site> = Vec::new();
+
+        while let Some(barracks_type) = self.get_next_unit_to_train(context) {
+            if gold_available < barracks_type.cost_to_train() {
+                break;
+            }
+
+            let train_site = context.sites.iter().find(|site| {
+                site.is_barracks()
+                    && site.owner().unwrap() == Owner::Friendly
+                    && site.barracks().barracks_type == barracks_type
+                    && site.barracks().turns_to_train == 0
+                    && !train_sites.iter().any(|site2| site.id == site2.id)
+            });
+
+            if let Some(train_site) = train_site {
+                train_sites.push(train_site);
+                gold_available -= barracks_type.cost_to_train();
+            } else {
+                break;
+            }
+        }
+
+        TrainCommand::for_sites(train_sites)
+    }
+}
+
+pub struct DefaultStrategy {
+    pub params: StrategyParams,
+}
+
+/// Builds a knight barracks first and hands over to the default strategy once it stands.
+#[cfg(feature = "offline")]
+pub struct RushStrategy {
+    pub fallback: DefaultStrategy,
+}
+
+/// Decisions of the default strategy composed as a behavior tree, see `BehaviorTreeStrategy::build_tree`.
+#[cfg(feature = "offline")]
+pub struct BehaviorTreeStrategy {
+    pub fallback: Rc<DefaultStrategy>,
+    tree: RefCell<BehaviorTree>,
+}
+
+/// Default strategy choosing where to build barracks and towers by simulating the candidates, see `MonteCarloSearch`.
+pub struct SearchStrategy {
+    pub fallback: DefaultStrategy,
+}
+
+pub fn strategy_by_name(name: &str, params: StrategyParams) -> Option<Box<dyn Strategy>> {
+    match name {
+        "default" => Some(Box::new(DefaultStrategy::new(params))),
+        #[cfg(feature = "offline")]
+        "rush" => Some(Box::new(RushStrategy::new(params))),
+        #[cfg(feature = "offline")]
+        "tree" => Some(Box::new(BehaviorTreeStrategy::new(params))),
+        "search" => Some(Box::new(SearchStrategy::new(params))),
+        _ => None,
+    }
+}
+
+/// Next structure in the build order of the default strategy.
+#[derive(Copy, Clone)]
+enum BuildStep {
+    /// first tower against incoming knights, built right where the queen is
+    UrgentTower,
+    Mine(MinePlan),
+    Barracks(BarracksType),
+    Tower,
+}
+
+/// Our structures the build order depends on, advanced by the steps planned ahead.
+struct BuildCounts {
+    towers: usize,
+    mines: usize,
+    knight_barracks: usize,
+    archer_barracks: usize,
+    giant_barracks: usize,
+}
+
+/// What the build order depends on besides our structures.
+struct BuildSituation {
+    knights_incoming: bool,
+    knight_is_close: bool,
+    knights_massing: bool,
+    enemy_has_towers: bool,
+    mine_plan: Option<MinePlan>,
+}
+
+impl BuildCounts {
+    fn new(context: &Context) -> BuildCounts {
+        let count = |build_type: BuildType| context.site_count(build_type, Owner::Friendly);
+
+        BuildCounts {
+            towers: count(BuildType::Tower),
+            mines: count(BuildType::Mine),
+            knight_barracks: count(BuildType::Barracks(BarracksType::Knight)),
+            archer_barracks: count(BuildType::Barracks(BarracksType::Archer)),
+            giant_barracks: count(BuildType::Barracks(BarracksType::Giant)),
+        }
+    }
+
+    fn add(&mut self, step: BuildStep) {
+        match step {
+            BuildStep::UrgentTower | BuildStep::Tower => self.towers += 1,
+            BuildStep::Mine(_) => self.mines += 1,
+            BuildStep::Barracks(BarracksType::Knight) => self.knight_barracks += 1,
+            BuildStep::Barracks(BarracksType::Archer) => self.archer_barracks += 1,
+            BuildStep::Barracks(BarracksType::Giant) => self.giant_barracks += 1,
+        }
+    }
+}
+
+impl DefaultStrategy {
+    pub fn new(params: StrategyParams) -> DefaultStrategy {
+        DefaultStrategy { params }
+    }
+
+    /// Closest site to build on, only sites covered by friendly towers while enemy knights close in.
+    pub fn get_closest_site_to_build<'a>(&self, context: &'a Context) -> Option<&'a Site> {
+        let under_threat = FleeTask::is_needed(context, self.params.knight_danger_turns);
+        context.closest_site(|site| self.is_site_to_build(context, site, under_threat))
+    }
+
+    fn is_site_to_build(&self, context: &Context, site: &Site, under_threat: bool) -> bool {
+        if !self.is_site_safe_to_build(context, site, under_threat) {
+            return false;
+        }
+
+        match &site.structure {
+            Structure::Barracks(barracks) => barracks.owner != Owner::Friendly,
+            Structure::Mine(mine) => mine.owner != Owner::Friendly,
+            Structure::Tower(_) => false,
+            Structure::NoStructure => true,
+        }
+    }
+
+    /// Whether the site is a friendly mine below the maximum rate the queen can still upgrade.
+    fn is_mine_to_upgrade(&self, context: &Context, site: &Site, under_threat: bool) -> bool {
+        let below_max_rate = matches!(&site.structure, Structure::Mine(mine)
+            if mine.owner == Owner::Friendly && mine.mining_rate < context.estimated_max_mining_rate(site));
+
+        below_max_rate && self.is_site_safe_to_build(context, site, under_threat)
+    }
+
+    /// Whether the site is within the build radius, out of range of enemy towers and, while under threat, covered by friendly towers.
+    fn is_site_safe_to_build(&self, context: &Context, site: &Site, under_threat: bool) -> bool {
+        if (site.position - context.start_position).len() > self.params.build_radius {
+            return false;
+        }
+
+        if under_threat && context.towers_in_range(site.position, Owner::Friendly) == 0 {
+            return false;
+        }
+
+        let enemy_tower_in_range = context.sites.iter().any(|site2| {
+            site2.is_tower() &&
+                site2.tower().owner == Owner::Enemy &&
+                (site2.position - site.position).len() < site2.tower().attack_radius as f64
+        });
+
+        !enemy_tower_in_range
+    }
+
+    /**
+     * Site to build on or friendly mine to upgrade with the most valuable mine, see `plan_mine`.
+     * Upgrades are valued by the gold left in the site against the queen turns they take, like new
+     * mines. Among the mines paying off, safe sites are preferred, the value is raised by their
+     * safety on the influence map. None if no mine pays off.
+     **/
+    pub fn get_best_mine_plan(&self, context: &Context, influence: &InfluenceMap) -> Option<MinePlan> {
+        let under_threat = FleeTask::is_needed(context, self.params.knight_danger_turns);
+        let remaining_turns = MAX_TURNS.saturating_sub(context.history.turn_count());
+        let safety_value = |plan: &MinePlan| plan.value + influence.safety(context.site_by_id(plan.site_id)) * self.params.mine_safety_value;
+
+        context.sites.iter()
+            .filter(|site| self.is_site_to_build(context, site, under_threat) || self.is_mine_to_upgrade(context, site, under_threat))
+            .map(|site| plan_mine(context, site, travel_turns(context, site), remaining_turns, self.params.queen_turn_value))
+            .filter(|plan| plan.value > 0.0)
+            .max_by(|plan1, plan2| safety_value(plan1).total_cmp(&safety_value(plan2)))
+    }
+
+    /// Builds the planned mine if the queen is at its site, walks there otherwise.
+    fn get_mine_task(&self, context: &Context, plan: MinePlan) -> Box<dyn Task> {
+        if plan.site_id == context.touched_site_id {
+            Box::new(BuildMineTask::new(context.site_by_id(plan.site_id), self.params.knight_danger_turns, plan.rate))
+        } else {
+            Box::new(MoveToBuildSiteTask::new(plan.site_id, self.params.knight_danger_turns))
+        }
+    }
+
+    /**
+     * Site to build the next tower on: towers on the frontier, where both players have influence,
+     * cover more of our territory than towers in its interior. The contestedness of each site
+     * on the influence map is weighed against the queen turns spent walking there.
+     **/
+    pub fn get_tower_site<'a>(&self, context: &'a Context, influence: &InfluenceMap) -> Option<&'a Site> {
+        let under_threat = FleeTask::is_needed(context, self.params.knight_danger_turns);
+        let score = |site: &Site| {
+            influence.contestedness(site) * self.params.tower_frontier_value
+                - travel_turns(context, site) as f64 * self.params.queen_turn_value
+        };
+
+        context.sites.iter()
+            .filter(|site| self.is_site_to_build(context, site, under_threat))
+            .max_by(|site1, site2| score(site1).total_cmp(&score(site2)))
+    }
+
+    /// Builds a tower if the queen is at the best tower site, walks there otherwise.
+    fn get_tower_task(&self, context: &Context, build_site: &Site) -> Box<dyn Task> {
+        match self.get_tower_site(context, &InfluenceMap::new(context)) {
+            Some(site) if site.id != build_site.id => Box::new(MoveToBuildSiteTask::new(site.id, self.params.knight_danger_turns)),
+            _ => Box::new(BuildTowerTask::new(build_site, self.params.tower_radius_goal)),
+        }
+    }
+
+    /**
+     * Friendly tower to top up: its attack radius melts below the upkeep radius before the queen
+     * could be back from her next build, and it is close enough for the walk to pay off.
+     * The closest such tower is chosen.
+     **/
+    pub fn get_tower_to_upkeep<'a>(&self, context: &'a Context) -> Option<&'a Site> {
+        context.sites.iter()
+            .filter(|site| site.is_tower() && site.tower().owner == Owner::Friendly)
+            .map(|site| (site, travel_turns(context, site)))
+            .filter(|(site, travel_turns)| {
+                let turns_left = site.tower_turns_above_radius(self.params.tower_upkeep_radius) as usize;
+
+                *travel_turns <= self.params.tower_upkeep_travel_turns
+                    && turns_left <= travel_turns + self.params.tower_upkeep_travel_turns
+            })
+            .min_by_key(|(_, travel_turns)| *travel_turns)
+            .map(|(site, _)| site)
+    }
+
+    fn get_build_situation(&self, context: &Context) -> BuildSituation {
+        let predictor = ThreatPredictor::new(context, PREDICTION_TURNS);
+        let knight_turns = predictor.turns_until_knight_reaches_queen(context);
+        let knight_rush_is_close = self.turns_until_knight_rush(context).is_some_and(|turns| turns <= self.params.rush_warning_turns);
+
+        BuildSituation {
+            knights_incoming: context.unit_count(UnitType::Knight, Owner::Enemy) > 0 || knight_rush_is_close,
+            knight_is_close: knight_turns.is_some_and(|turns| turns <= self.params.knight_danger_turns),
+            knights_massing: self.enemy_knights_near_base(context) >= self.params.archer_knight_count,
+            enemy_has_towers: context.site_count(BuildType::Tower, Owner::Enemy) > 0,
+            mine_plan: self.get_best_mine_plan(context, &InfluenceMap::new(context)),
+        }
+    }
+
+    /// Next structure in the build order given the structures we already have.
+    fn get_next_build_step(&self, counts: &BuildCounts, situation: &BuildSituation) -> BuildStep {
+        let params = &self.params;
+
+        // build tower if there are enemy knights or some are about to come and we have no towers
+        if situation.knights_incoming && counts.towers == 0 {
+            return BuildStep::UrgentTower;
+        }
+
+        // build a minimum number of mines
+        if counts.mines < params.min_mine_count && !situation.knight_is_close {
+            if let Some(plan) = situation.mine_plan {
+                return BuildStep::Mine(plan);
+            }
+        }
+
+        // build knights barracks
+        if counts.knight_barracks == 0 {
+            return BuildStep::Barracks(BarracksType::Knight);
+        }
+
+        // answer enemy knights massing near our base with archers
+        if situation.knights_massing && counts.archer_barracks == 0 {
+            return BuildStep::Barracks(BarracksType::Archer);
+        }
+
+        // build a minimum amount of towers
+        if counts.towers < params.min_tower_count {
+            return BuildStep::Tower;
+        }
+
+        // build giant barracks if enemy builds towers
+        if situation.enemy_has_towers && counts.giant_barracks == 0 {
+            return BuildStep::Barracks(BarracksType::Giant);
+        }
+
+        // build an additional amount of mines if no enemy knight is close
+        if counts.mines < params.max_mine_count && !situation.knight_is_close {
+            if let Some(plan) = situation.mine_plan {
+                return BuildStep::Mine(plan);
+            }
+        }
+
+        BuildStep::Tower
+    }
+
+    /**
+     * Barracks and towers coming up next in the build order, as long as no enemy knight is around
+     * to make the queen change her mind. Mines end the steps, their sites are chosen by value.
+     **/
+    fn get_planned_steps(&self, context: &Context) -> Vec<BuildStep> {
+        let mut steps = Vec::new();
+
+        if context.unit_count(UnitType::Knight, Owner::Enemy) > 0 || self.get_tower_to_upkeep(context).is_some() {
+            return steps;
+        }
+
+        let mut counts = BuildCounts::new(context);
+        let situation = self.get_build_situation(context);
+
+        while steps.len() < self.params.plan_length {
+            let step = self.get_next_build_step(&counts, &situation);
+
+            if !matches!(step, BuildStep::Barracks(_) | BuildStep::Tower) {
+                break;
+            }
+
+            counts.add(step);
+            steps.push(step);
+        }
+
+        steps
+    }
+
+    /**
+     * Site of a friendly tower lost in the last turns that is still empty and within the walking
+     * range of tower upkeep. The closest such site is chosen.
+     **/
+    pub fn get_tower_to_rebuild<'a>(&self, context: &'a Context) -> Option<&'a Site> {
+        let since_turn = context.history.turn_count().saturating_sub(TOWER_REBUILD_TURNS);
+
+        context.history.events_since(since_turn)
+            .filter(|event| event.owner == Owner::Friendly && event.kind == EventKind::StructureLost(BuildType::Tower))
+            .map(|event| context.site_by_id(event.site_id))
+            .filter(|site| matches!(site.structure, Structure::NoStructure) && self.is_site_to_build(context, site, false))
+            .map(|site| (site, travel_turns(context, site)))
+            .filter(|(_, travel_turns)| *travel_turns <= self.params.tower_upkeep_travel_turns)
+            .min_by_key(|(_, travel_turns)| *travel_turns)
+            .map(|(site, _)| site)
+    }
+
+    /// Enemy knights within the build radius around our start position.
+    pub fn enemy_knights_near_base(&self, context: &Context) -> usize {
+        context.units.iter()
+            .filter(|unit| {
+                unit.owner == Owner::Enemy
+                    && unit.unit_type == UnitType::Knight
+                    && (unit.position - context.start_position).len() <= self.params.build_radius
+            })
+            .count()
+    }
+
+    /// Turns until knights of the next enemy training can reach the queen at the earliest.
+    pub fn turns_until_knight_rush(&self, context: &Context) -> Option<usize> {
+        let queen_position = context.friendly_queen().position;
+        let affordable_turns = context.turns_until_enemy_affords(BarracksType::Knight)?;
+
+        context.sites.iter()
+            .filter(|site| {
+                site.is_barracks()
+                    && site.barracks().owner == Owner::Enemy
+                    && site.barracks().barracks_type == BarracksType::Knight
+            })
+            .map(|site| {
+                let start_turns = affordable_turns.max(site.barracks().turns_to_train as usize);
+                let walk_turns = ((site.position - queen_position).len() / UnitType::Knight.speed()).ceil() as usize;
+
+                start_turns + BarracksType::Knight.turns_to_train() as usize + walk_turns
+            })
+            .min()
+    }
+}
+
+impl Default for DefaultStrategy {
+    fn default() -> Self {
+        DefaultStrategy::new(StrategyParams::default())
+    }
+}
+
+impl Strategy for DefaultStrategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task> {
+        // keep the towers from melting away
+        if let Some(tower_site) = self.get_tower_to_upkeep(context) {
+            return Box::new(TowerUpkeepTask::new(tower_site, self.params.tower_radius_goal));
+        }
+
+        let build_site = self.get_closest_site_to_build(context);
+
+        if build_site.is_none() {
+            // get behind friendly towers before the knights reach the queen
+            if FleeTask::is_needed(context, self.params.knight_danger_turns) {
+                return Box::new(FleeTask::new(self.params.knight_danger_turns));
+            }
+
+            return Box::new(WaitTask::new());
+        }
+
+        let build_site = build_site.unwrap();
+        let counts = BuildCounts::new(context);
+        let situation = self.get_build_situation(context);
+
+        match self.get_next_build_step(&counts, &situation) {
+            // mines are planned for a site of their own, which may be a friendly mine to upgrade
+            BuildStep::Mine(plan) => self.get_mine_task(context, plan),
+            _ if context.touched_site_id != build_site.id => {
+                Box::new(MoveToBuildSiteTask::new(build_site.id, self.params.knight_danger_turns))
+            },
+            BuildStep::UrgentTower => Box::new(BuildTowerTask::new(build_site, self.params.tower_radius_goal)),
+            BuildStep::Barracks(barracks_type) => Box::new(BuildBarracksTask::new(build_site, barracks_type)),
+            BuildStep::Tower => self.get_tower_task(context, build_site),
+        }
+    }
+
+    /**
+     * Flees from knights when out of tower range, builds a first tower as soon as knights come in
+     * and rebuilds towers just lost. Whatever the queen was doing is resumed afterwards.
+     **/
+    fn get_urgent_task(&self, context: &Context) -> Option<Box<dyn Task>> {
+        let queen_position = context.friendly_queen().position;
+
+        if FleeTask::is_needed(context, self.params.knight_danger_turns) && context.towers_in_range(queen_position, Owner::Friendly) == 0 {
+            return Some(Box::new(FleeTask::new(self.params.knight_danger_turns)));
+        }
+
+        let knight_rush_is_close = self.turns_until_knight_rush(context).is_some_and(|turns| turns <= self.params.rush_warning_turns);
+        let knights_incoming = context.unit_count(UnitType::Knight, Owner::Enemy) > 0 || knight_rush_is_close;
+        let first_tower_needed = knights_incoming && context.site_count(BuildType::Tower, Owner::Friendly) == 0;
+
+        let tower_site = first_tower_needed.then(|| self.get_closest_site_to_build(context))
+            .flatten()
+            .or_else(|| self.get_tower_to_rebuild(context));
+
+        tower_site.map(|site| {
+            let task = BuildTowerTask::new(site, self.params.tower_radius_goal);
+            Box::new(PriorityTask::new(Box::new(task), TaskPriority::High)) as Box<dyn Task>
+        })
+    }
+
+    /// Visits the sites of several barracks and towers in the order walking the least, see `plan_tour`.
+    fn get_next_plan(&self, context: &Context, _timer: &TurnTimer) -> Vec<Box<dyn Task>> {
+        let steps = self.get_planned_steps(context);
+        let sites: Vec<&Site> = context.sites.iter()
+            .filter(|site| self.is_site_to_build(context, site, false))
+            .collect();
+        let tour = plan_tour(context.friendly_queen().position, &sites, steps.len());
+
+        if steps.len() < 2 || tour.len() < steps.len() {
+            return vec![self.get_next_task(context)];
+        }
+
+        let mut tasks: Vec<Box<dyn Task>> = Vec::new();
+
+        for (step, site_id) in steps.into_iter().zip(tour) {
+            let site = context.site_by_id(site_id);
+
+            tasks.push(Box::new(MoveToBuildSiteTask::new(site_id, self.params.knight_danger_turns)));
+            tasks.push(match step {
+                BuildStep::Barracks(barracks_type) => Box::new(BuildBarracksTask::new(site, barracks_type)),
+                _ => Box::new(BuildTowerTask::new(site, self.params.tower_radius_goal)),
+            });
+        }
+
+        tasks
+    }
+
+    fn get_next_unit_to_train(&self, context: &Context) -> Option<BarracksType> {
+        let enemy_tower_count = context.site_count(BuildType::Tower, Owner::Enemy);
+        let friendly_knight_barracks_count = context.site_count(BuildType::Barracks(BarracksType::Knight), Owner::Friendly);
+        let friendly_giant_barracks_count = context.site_count(BuildType::Barracks(BarracksType::Giant), Owner::Friendly);
+        let friendly_archer_barracks_count = context.site_count(BuildType::Barracks(BarracksType::Archer), Owner::Friendly);
+        let friendly_giant_count = context.unit_count(UnitType::Giant, Owner::Friendly);
+        let friendly_archer_count = context.unit_count(UnitType::Archer, Owner::Friendly);
+        let enemy_knights_near_base = self.enemy_knights_near_base(context);
+
+        // about one archer per two knights, archers take a while to shoot a knight down
+        if enemy_knights_near_base >= self.params.archer_knight_count
+            && friendly_archer_barracks_count > 0
+            && friendly_archer_count * 2 < enemy_knights_near_base {
+            return Some(BarracksType::Archer);
+        }
+
+        if enemy_tower_count > 0 && friendly_giant_barracks_count > 0 && friendly_giant_count == 0 {
+            return Some(BarracksType::Giant);
+        }
+
+        if friendly_knight_barracks_count > 0 {
+            return Some(BarracksType::Knight);
+        }
+
+        None
+    }
+}
+
+#[cfg(feature = "offline")]
+impl RushStrategy {
+    pub fn new(params: StrategyParams) -> RushStrategy {
+        RushStrategy {
+            fallback: DefaultStrategy::new(params),
+        }
+    }
+}
+
+#[cfg(feature = "offline")]
+impl Default for RushStrategy {
+    fn default() -> Self {
+        RushStrategy::new(StrategyParams::default())
+    }
+}
+
+#[cfg(feature = "offline")]
+impl Strategy for RushStrategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task> {
+        let build_site = self.fallback.get_closest_site_to_build(context);
+
+        match build_site {
+            Some(site) if context.touched_site_id != site.id => Box::new(MoveToBuildSiteTask::new(site.id, self.fallback.params.knight_danger_turns)),
+            Some(site) => Box::new(BuildBarracksTask::new(site, BarracksType::Knight)),
+            None => Box::new(WaitTask::new()),
+        }
+    }
+
+    fn get_next_unit_to_train(&self, context: &Context) -> Option<BarracksType> {
+        self.fallback.get_next_unit_to_train(context)
+    }
+
+    fn get_next_strategy(&self, context: &Context) -> Option<Box<dyn Strategy>> {
+        if context.site_count(BuildType::Barracks(BarracksType::Knight), Owner::Friendly) > 0 {
+            Some(Box::new(DefaultStrategy::new(self.fallback.params.clone())))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "offline")]
+impl BehaviorTreeStrategy {
+    pub fn new(params: StrategyParams) -> BehaviorTreeStrategy {
+        let fallback = Rc::new(DefaultStrategy::new(params));
+        let tree = RefCell::new(BehaviorTreeStrategy::build_tree(&fallback));
+
+        BehaviorTreeStrategy { fallback, tree }
+    }
+
+    /**
+     * Escapes and defends first, then follows the build order of the default strategy: mines,
+     * knight barracks, archer barracks against massing knights, towers, giant barracks against
+     * enemy towers, more mines and towers at last.
+     **/
+    pub fn build_tree(strategy: &Rc<DefaultStrategy>) -> BehaviorTree {
+        let params = strategy.params.clone();
+        let danger_turns = params.knight_danger_turns;
+
+        let count = |build_type: BuildType| move |context: &Context| context.site_count(build_type, Owner::Friendly);
+        let towers = count(BuildType::Tower);
+        let mines = count(BuildType::Mine);
+        let knight_barracks = count(BuildType::Barracks(BarracksType::Knight));
+        let archer_barracks = count(BuildType::Barracks(BarracksType::Archer));
+        let giant_barracks = count(BuildType::Barracks(BarracksType::Giant));
+
+        let knight_is_close = move |context: &Context| {
+            let predictor = ThreatPredictor::new(context, PREDICTION_TURNS);
+            predictor.turns_until_knight_reaches_queen(context).is_some_and(|turns| turns <= danger_turns)
+        };
+
+        let closest_site = {
+            let strategy = strategy.clone();
+            move |context: &Context| strategy.get_closest_site_to_build(context).map(|site| site.id)
+        };
+        let mine_plan = {
+            let strategy = strategy.clone();
+            move |context: &Context| strategy.get_best_mine_plan(context, &InfluenceMap::new(context))
+        };
+        let tower_site = {
+            let strategy = strategy.clone();
+            move |context: &Context| strategy.get_tower_site(context, &InfluenceMap::new(context)).map(|site| site.id)
+        };
+
+        let radius_goal = params.tower_radius_goal;
+        let build_tower = move |_: &Context, site: &Site| Box::new(BuildTowerTask::new(site, radius_goal)) as Box<dyn Task>;
+        let build_barracks = |barracks_type: BarracksType| {
+            move |_: &Context, site: &Site| Box::new(BuildBarracksTask::new(site, barracks_type)) as Box<dyn Task>
+        };
+        let mine = move |context: &Context| {
+            mine_plan(context).map(|plan| {
+                let site = context.site_by_id(plan.site_id);
+                (plan.site_id, Box::new(BuildMineTask::new(site, danger_turns, plan.rate)) as Box<dyn Task>)
+            })
+        };
+
+        let escape = {
+            let strategy = strategy.clone();
+            let condition = move |context: &Context| {
+                // keeps moving between covered positions while there is nothing to build
+                FleeTask::is_needed(context, danger_turns)
+                    && (context.towers_in_range(context.friendly_queen().position, Owner::Friendly) == 0
+                        || strategy.get_closest_site_to_build(context).is_none())
+            };
+
+            sequence("escape", vec![
+                Box::new(Condition::new(condition)),
+                Box::new(TaskAction::new("flee", move |_| Some(Box::new(FleeTask::new(danger_turns)) as Box<dyn Task>))),
+            ])
+        };
+
+        let defend = {
+            let strategy = strategy.clone();
+            let condition = move |context: &Context| {
+                let rush_warning_turns = strategy.params.rush_warning_turns;
+                let knight_rush_is_close = strategy.turns_until_knight_rush(context).is_some_and(|turns| turns <= rush_warning_turns);
+                (context.unit_count(UnitType::Knight, Owner::Enemy) > 0 || knight_rush_is_close) && towers(context) == 0
+            };
+
+            sequence("defend", vec![
+                Box::new(Condition::new(condition)),
+                walk_and_build("build first tower", on_site(closest_site.clone(), build_tower), danger_turns),
+            ])
+        };
+
+        let upkeep = {
+            let strategy = strategy.clone();
+            Box::new(TaskAction::new("upkeep tower", move |context| {
+                strategy.get_tower_to_upkeep(context).map(|site| Box::new(TowerUpkeepTask::new(site, radius_goal)) as Box<dyn Task>)
+            }))
+        };
+
+        let min_mines = params.min_mine_count;
+        let max_mines = params.max_mine_count;
+        let min_towers = params.min_tower_count;
+        let archer_knight_count = params.archer_knight_count;
+        let knights_massing = {
+            let strategy = strategy.clone();
+            move |context: &Context| strategy.enemy_knights_near_base(context) >= archer_knight_count
+        };
+
+        let mines_below = |limit: usize| move |context: &Context| mines(context) < limit;
+        let no_close_knight = || Box::new(Inverter::new(Box::new(Condition::new(knight_is_close)))) as Box<dyn Node>;
+
+        let root = Selector::new("queen", vec![
+            escape,
+            defend,
+            upkeep,
+            sequence("economy", vec![
+                Box::new(Condition::new(mines_below(min_mines))),
+                no_close_knight(),
+                walk_and_build("build mine", mine.clone(), danger_turns),
+            ]),
+            sequence("army", vec![
+                Box::new(Condition::new(move |context| knight_barracks(context) == 0)),
+                walk_and_build("build knight barracks", on_site(closest_site.clone(), build_barracks(BarracksType::Knight)), danger_turns),
+            ]),
+            sequence("anti knights", vec![
+                Box::new(Condition::new(move |context| knights_massing(context) && archer_barracks(context) == 0)),
+                walk_and_build("build archer barracks", on_site(closest_site.clone(), build_barracks(BarracksType::Archer)), danger_turns),
+            ]),
+            sequence("defense", vec![
+                Box::new(Condition::new(move |context| towers(context) < min_towers)),
+                walk_and_build("build tower", on_site(tower_site.clone(), build_tower), danger_turns),
+            ]),
+            sequence("siege", vec![
+                Box::new(Condition::new(move |context| {
+                    context.site_count(BuildType::Tower, Owner::Enemy) > 0 && giant_barracks(context) == 0
+                })),
+                walk_and_build("build giant barracks", on_site(closest_site, build_barracks(BarracksType::Giant)), danger_turns),
+            ]),
+            sequence("expansion", vec![
+                Box::new(Condition::new(mines_below(max_mines))),
+                no_close_knight(),
+                walk_and_build("build mine", mine, danger_turns),
+            ]),
+            walk_and_build("fortification", on_site(tower_site, build_tower), danger_turns),
+        ]);
+
+        BehaviorTree::new(Box::new(root))
+    }
+}
+
+#[cfg(feature = "offline")]
+impl Strategy for BehaviorTreeStrategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task> {
+        let command = self.tree.borrow_mut().tick(context);
+        Box::new(CommandTask::new(context, command))
+    }
+
+    fn get_next_unit_to_train(&self, context: &Context) -> Option<BarracksType> {
+        self.fallback.get_next_unit_to_train(context)
+    }
+
+    fn get_debug_info(&self) -> Option<String> {
+        Some(self.tree.borrow().active_path())
+    }
+}
+
+impl SearchStrategy {
+    pub fn new(params: StrategyParams) -> SearchStrategy {
+        SearchStrategy {
+            fallback: DefaultStrategy::new(params),
+        }
+    }
+
+    /**
+     * Actions to choose from when the next step of the build order is a barracks or a tower:
+     * building it on one of the sites closest to the queen, or retreating behind friendly towers
+     * while enemy knights are around. Empty when the default strategy decides alone.
+     **/
+    pub fn get_search_candidates(&self, context: &Context) -> Vec<MacroAction> {
+        let params = &self.fallback.params;
+
+        if self.fallback.get_tower_to_upkeep(context).is_some() {
+            return Vec::new();
+        }
+
+        let counts = BuildCounts::new(context);
+        let situation = self.fallback.get_build_situation(context);
+        let build_type = match self.fallback.get_next_build_step(&counts, &situation) {
+            BuildStep::UrgentTower | BuildStep::Tower => BuildType::Tower,
+            BuildStep::Barracks(barracks_type) => BuildType::Barracks(barracks_type),
+            BuildStep::Mine(_) => return Vec::new(),
+        };
+
+        let under_threat = FleeTask::is_needed(context, params.knight_danger_turns);
+        let mut sites: Vec<&Site> = context.sites.iter()
+            .filter(|site| self.fallback.is_site_to_build(context, site, under_threat))
+            .collect();
+
+        sites.sort_by_key(|site| travel_turns(context, site));
+        sites.truncate(SEARCH_SITE_COUNT);
+
+        let mut candidates: Vec<MacroAction> = sites.into_iter()
+            .map(|site| MacroAction::Build(BuildCommand::for_site(site, build_type)))
+            .collect();
+
+        if context.unit_count(UnitType::Knight, Owner::Enemy) > 0 {
+            if let Some(position) = FleeTask::new(params.knight_danger_turns).covered_position(context) {
+                candidates.push(MacroAction::Move(position));
+            }
+        }
+
+        candidates
+    }
+
+    /// Tasks carrying out an action chosen by the search.
+    pub fn plan_action(&self, context: &Context, action: MacroAction) -> Vec<Box<dyn Task>> {
+        let params = &self.fallback.params;
+
+        match action {
+            MacroAction::Build(build_command) => {
+                let site = context.site_by_id(build_command.site_id);
+                let build_task: Box<dyn Task> = match build_command.build_type {
+                    BuildType::Tower => Box::new(BuildTowerTask::new(site, params.tower_radius_goal)),
+                    BuildType::Barracks(barracks_type) => Box::new(BuildBarracksTask::new(site, barracks_type)),
+                    BuildType::Mine => Box::new(BuildMineTask::new(site, params.knight_danger_turns, context.estimated_max_mining_rate(site))),
+                };
+
+                vec![Box::new(MoveToBuildSiteTask::new(site.id, params.knight_danger_turns)), build_task]
+            },
+            MacroAction::Move(position) => vec![Box::new(MoveToPositionTask::new(position))],
+        }
+    }
+}
+
+impl Default for SearchStrategy {
+    fn default() -> Self {
+        SearchStrategy::new(StrategyParams::default())
+    }
+}
+
+impl Strategy for SearchStrategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task> {
+        self.fallback.get_next_task(context)
+    }
+
+    fn get_next_unit_to_train(&self, context: &Context) -> Option<BarracksType> {
+        self.fallback.get_next_unit_to_train(context)
+    }
+
+    fn get_urgent_task(&self, context: &Context) -> Option<Box<dyn Task>> {
+        self.fallback.get_urgent_task(context)
+    }
+
+    /// Walks to the site of the best candidate and builds there, or retreats, see `get_search_candidates`.
+    fn get_next_plan(&self, context: &Context, timer: &TurnTimer) -> Vec<Box<dyn Task>> {
+        let candidates = self.get_search_candidates(context);
+
+        if candidates.len() < 2 {
+            return self.fallback.get_next_plan(context, timer);
+        }
+
+        let search = MonteCarloSearch::new(context.history.turn_count() as u64);
+
+        match search.best_action(context, &candidates, timer) {
+            Some(action) => self.plan_action(context, action),
+            None => self.fallback.get_next_plan(context, timer),
+        }
+    }
+}
+
+/// Branch of the tree, its conditions are checked when it starts and its tasks run to completion.
+#[cfg(feature = "offline")]
+fn sequence(name: &'static str, children: Vec<Box<dyn Node>>) -> Box<dyn Node> {
+    Box::new(Sequence::with_memory(name, children))
+}
+
+/// Site chosen by `site_id` together with the task from `build` building on it.
+#[cfg(feature = "offline")]
+fn on_site(
+    site_id: impl Fn(&Context) -> Option<i32> + 'static,
+    build: impl Fn(&Context, &Site) -> Box<dyn Task> + 'static,
+) -> impl Fn(&Context) -> Option<(i32, Box<dyn Task>)> + Clone + 'static {
+    let build = Rc::new(build);
+    let site_id = Rc::new(site_id);
+
+    move |context| site_id(context).map(|id| (id, build(context, context.site_by_id(id))))
+}
+
+/**
+ * Walks to the site chosen by `target` and then builds on it. The site and its build task are
+ * chosen once as the walk starts and kept for the build, so the queen builds where she went even
+ * if another site is chosen by then.
+ **/
+#[cfg(feature = "offline")]
+fn walk_and_build(
+    name: &'static str,
+    target: impl Fn(&Context) -> Option<(i32, Box<dyn Task>)> + 'static,
+    knight_danger_turns: usize,
+) -> Box<dyn Node> {
+    let build_task: Rc<RefCell<Option<Box<dyn Task>>>> = Rc::new(RefCell::new(None));
+    let chosen_build_task = build_task.clone();
+
+    sequence(name, vec![
+        Box::new(TaskAction::new("walk", move |context| {
+            let (site_id, task) = target(context)?;
+            *chosen_build_task.borrow_mut() = Some(task);
+            Some(Box::new(MoveToBuildSiteTask::new(site_id, knight_danger_turns)) as Box<dyn Task>)
+        })),
+        Box::new(TaskAction::new("build", move |_| build_task.borrow_mut().take())),
+    ])
+}
+
+/// Turns the queen walks until she touches the site.
+fn travel_turns(context: &Context, site: &Site) -> usize {
+    let gap = (site.position - context.friendly_queen().position).len() - (site.radius as f64) - UnitType::Queen.radius();
+    (gap.max(0.0) / UnitType::Queen.speed()).ceil() as usize
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use crate::path::{MAP_HEIGHT, MAP_WIDTH};
+use crate::point::Vector2;
+use crate::site::Site;
+
+/// Distance within which a site counts as placed at the mirrored position of another.
+const MIRROR_TOLERANCE: f64 = 5.0;
+
+/// Position point-symmetric to the given one around the map center.
+pub fn mirror(position: Vector2) -> Vector2 {
+    Vector2 {
+        x: MAP_WIDTH - position.x,
+        y: MAP_HEIGHT - position.y,
+    }
+}
+
+/**
+ * Pairs of sites placed point-symmetrically around the map center. Both sites of a pair have the
+ * same radius and start with the same gold and maximum mining rate, so whatever is known about one
+ * side of the map holds for the other side until the sites are mined.
+ **/
+pub struct Symmetry {
+    mirror_ids: HashMap<i32, i32>,
+}
+
+impl Symmetry {
+    pub fn new(sites: &[Site]) -> Symmetry {
+        let mut mirror_ids = HashMap::new();
+
+        for site in sites {
+            let position = mirror(site.position);
+            let mirrored = sites.iter().find(|other| {
+                other.id != site.id
+                    && other.radius == site.radius
+                    && (other.position - position).len() < MIRROR_TOLERANCE
+            });
+
+            if let Some(mirrored) = mirrored {
+                mirror_ids.insert(site.id, mirrored.id);
+            }
+        }
+
+        Symmetry { mirror_ids }
+    }
+
+    /// Id of the site paired with the given one, None if the map is not symmetric there.
+    pub fn mirror_id(&self, site_id: i32) -> Option<i32> {
+        self.mirror_ids.get(&site_id).copied()
+    }
+}
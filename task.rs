@@ -1,33 +1,92 @@
-trait Task {
+use crate::command::{BuildCommand, BuildType, QueenCommand};
+use crate::context::Context;
+use crate::owner::Owner;
+use crate::path::{PathPlanner, MAP_HEIGHT, MAP_WIDTH};
+use crate::point::Vector2;
+use crate::site::{BarracksType, Site, Structure};
+use crate::threat::{ThreatPredictor, PREDICTION_TURNS};
+use crate::unit::UnitType;
+
+/// How urgent a task is, tasks of a higher priority preempt the current one.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TaskPriority {
+    Normal,
+    /// defending the base
+    High,
+    /// saving the queen
+    Critical,
+}
+
+pub trait Task {
     fn is_task_done(&self, context: &Context) -> bool;
     fn get_next_command(&self, context: &Context) -> QueenCommand;
+
+    fn priority(&self) -> TaskPriority {
+        TaskPriority::Normal
+    }
 }
 
-struct BuildMineTask {
-    site_id: i32,
+pub struct BuildMineTask {
+    pub site_id: i32,
+    pub knight_danger_turns: usize,
+    /// upgraded until this mining rate or the maximum rate of the site
+    pub rate_goal: i32,
 }
 
-struct BuildBarracksTask {
-    site_id: i32,
-    barracks_type: BarracksType,
+pub struct BuildBarracksTask {
+    pub site_id: i32,
+    pub barracks_type: BarracksType,
 }
 
-struct BuildTowerTask {
-    site_id: i32,
+pub struct BuildTowerTask {
+    pub site_id: i32,
+    pub radius_goal: i32,
 }
 
-struct MoveToBuildSiteTask {
-    site_id: i32,
+pub struct MoveToBuildSiteTask {
+    pub site_id: i32,
+    pub knight_danger_turns: usize,
 }
 
-struct WaitTask {
+/// Walks the queen to a position, e.g. one chosen by the search.
+pub struct MoveToPositionTask {
+    pub position: Vector2,
+}
+
+/// Retreats the queen behind friendly towers while enemy knights close in.
+pub struct FleeTask {
+    pub knight_danger_turns: usize,
+}
 
+/// Walks back to a friendly tower and upgrades it until its attack radius exceeds the goal again.
+pub struct TowerUpkeepTask {
+    pub site_id: i32,
+    pub radius_goal: i32,
+}
+
+pub struct WaitTask {
+
+}
+
+/// Issues a command for a single turn, e.g. one decided by a behavior tree.
+pub struct CommandTask {
+    pub command: QueenCommand,
+    /// turn count of the history when the command was decided
+    turn: usize,
+}
+
+/// Runs a task with a raised priority, e.g. a tower build defending the base.
+pub struct PriorityTask {
+    pub task: Box<dyn Task>,
+    pub priority: TaskPriority,
 }
 
 impl BuildMineTask {
-    fn new(site: &Site) -> BuildMineTask {
+    pub fn new(site: &Site, knight_danger_turns: usize, rate_goal: i32) -> BuildMineTask {
         BuildMineTask {
             site_id: site.id,
+            knight_danger_turns,
+            rate_goal,
         }
     }
 }
@@ -35,15 +94,16 @@ impl BuildMineTask {
 impl Task for BuildMineTask {
     fn is_task_done(&self, context: &Context) -> bool {
         let site = context.site_by_id(self.site_id);
-        let enemy_knight_dist = context.closest_unit_dist(UnitType::Knight, Owner::Enemy);
+        let predictor = ThreatPredictor::new(context, PREDICTION_TURNS);
+        let knight_turns = predictor.turns_until_knight_reaches_queen(context);
 
-        if enemy_knight_dist.unwrap_or(i32::MAX) < 300 {
+        if knight_turns.is_some_and(|turns| turns <= self.knight_danger_turns) {
             return true; // abort
         }
 
         match &site.structure {
             Structure::Mine(mine) => {
-                mine.owner == Owner::Friendly && mine.mining_rate == site.max_mining_rate
+                mine.owner == Owner::Friendly && mine.mining_rate >= self.rate_goal.min(site.max_mining_rate)
             },
             Structure::Tower(_) => true,  // abort
             _ => false,
@@ -59,7 +119,7 @@ impl Task for BuildMineTask {
 }
 
 impl BuildBarracksTask {
-    fn new(site: &Site, barracks_type: BarracksType) -> BuildBarracksTask {
+    pub fn new(site: &Site, barracks_type: BarracksType) -> BuildBarracksTask {
         BuildBarracksTask {
             site_id: site.id,
             barracks_type,
@@ -89,9 +149,10 @@ impl Task for BuildBarracksTask {
 }
 
 impl BuildTowerTask {
-    fn new(site: &Site) -> BuildTowerTask {
+    pub fn new(site: &Site, radius_goal: i32) -> BuildTowerTask {
         BuildTowerTask {
             site_id: site.id,
+            radius_goal,
         }
     }
 }
@@ -102,7 +163,7 @@ impl Task for BuildTowerTask {
 
         match &site.structure {
             Structure::Tower(tower) => {
-                tower.attack_radius > 350 || tower.owner != Owner::Friendly
+                tower.attack_radius > self.radius_goal || tower.owner != Owner::Friendly
             },
             _ => false,
         }
@@ -117,9 +178,10 @@ impl Task for BuildTowerTask {
 }
 
 impl MoveToBuildSiteTask {
-    fn new(site_id: i32) -> MoveToBuildSiteTask {
+    pub fn new(site_id: i32, knight_danger_turns: usize) -> MoveToBuildSiteTask {
         MoveToBuildSiteTask {
-            site_id
+            site_id,
+            knight_danger_turns,
         }
     }
 }
@@ -133,55 +195,229 @@ impl Task for MoveToBuildSiteTask {
             return true;  // abort
         }
 
+        if FleeTask::is_needed(context, self.knight_danger_turns) && context.towers_in_range(site.position, Owner::Friendly) == 0 {
+            return true;  // abort
+        }
+
         const QUEEN_SPEED: f64 = 60.0;
         const QUEEN_RADIUS: f64 = 30.0;
 
         let dist_to_target = (site.position - queen_position).len() - (site.radius as f64) - QUEEN_RADIUS;
 
-        eprintln!("touched site: {}", context.touched_site_id);
-        eprintln!("dist to target: {}", dist_to_target as i32);
-
         dist_to_target < QUEEN_SPEED + 10.0
         //context.touched_site_id == self.site_id
     }
 
     fn get_next_command(&self, context: &Context) -> QueenCommand {
         let target_site = context.site_by_id(self.site_id);
+        let queen_position = context.friendly_queen().position;
+
+        let planner = PathPlanner::new(context, self.site_id);
+        QueenCommand::Move(planner.next_target(queen_position, target_site.position, UnitType::Queen.speed()))
+    }
+}
+
+/// A position is reached once the queen is this close to it.
+pub const ARRIVAL_DISTANCE: f64 = 10.0;
+
+impl MoveToPositionTask {
+    pub fn new(position: Vector2) -> MoveToPositionTask {
+        MoveToPositionTask {
+            position,
+        }
+    }
+}
+
+impl Task for MoveToPositionTask {
+    fn is_task_done(&self, context: &Context) -> bool {
+        (context.friendly_queen().position - self.position).len() <= ARRIVAL_DISTANCE
+    }
 
-        if context.touched_site_id >= 0 {
-            let touched_site = context.site_by_id(context.touched_site_id);
-            let queen_position = context.friendly_queen().position;
+    fn get_next_command(&self, context: &Context) -> QueenCommand {
+        let queen_position = context.friendly_queen().position;
+        let planner = PathPlanner::new(context, -1);
+
+        QueenCommand::Move(planner.next_target(queen_position, self.position, UnitType::Queen.speed()))
+    }
+}
+
+/// Friendly towers are only retreated to if their range is at most this far away.
+const MAX_FLEE_DISTANCE: f64 = 180.0;
+
+impl FleeTask {
+    pub fn new(knight_danger_turns: usize) -> FleeTask {
+        FleeTask {
+            knight_danger_turns,
+        }
+    }
+
+    /// Whether enemy knights are about to reach the queen while the range of a friendly tower is close.
+    pub fn is_needed(context: &Context, knight_danger_turns: usize) -> bool {
+        let predictor = ThreatPredictor::new(context, PREDICTION_TURNS);
+        let knight_turns = predictor.turns_until_knight_reaches_queen(context);
+        let queen_position = context.friendly_queen().position;
+
+        // running is pointless when the cover is far, knights are faster than the queen
+        let cover_is_close = context.sites.iter().any(|site| {
+            site.is_tower()
+                && site.tower().owner == Owner::Friendly
+                && (site.position - queen_position).len() - (site.tower().attack_radius as f64) < MAX_FLEE_DISTANCE
+        });
+
+        knight_turns.is_some_and(|turns| turns <= knight_danger_turns) && cover_is_close
+    }
+
+    /**
+     * Picks the position to retreat to: behind a friendly tower as seen from the knights, or away
+     * from them. Candidates are scored by the number of covering towers, the distance to the
+     * knights predicted for the time the queen arrives and the way to get there.
+     **/
+    pub fn safe_position(&self, context: &Context) -> Vector2 {
+        const COVER_SCORE: f64 = 500.0;
+        const ENEMY_TOWER_PENALTY: f64 = 1000.0;
 
-            let to_touched_site = touched_site.position - queen_position;
-            let to_target_site = target_site.position - queen_position;
+        let queen_position = context.friendly_queen().position;
+        let queen_radius = UnitType::Queen.radius();
+        let queen_speed = UnitType::Queen.speed();
+        let predictor = ThreatPredictor::new(context, PREDICTION_TURNS);
+
+        let knights: Vec<Vector2> = predictor.units_after(self.knight_danger_turns).iter()
+            .filter(|unit| unit.unit_type == UnitType::Knight)
+            .map(|unit| unit.position)
+            .collect();
 
-            let threshold = (20.0 as f64).to_radians();
+        let threat_center = if knights.is_empty() {
+            queen_position
+        } else {
+            knights.iter().fold(Vector2 { x: 0.0, y: 0.0 }, |sum, knight| sum + *knight).div(knights.len() as f64)
+        };
 
-            if Vector2::angle(to_touched_site, to_target_site) < threshold {
-                let around_cw = to_touched_site.perp_cw().norm();
-                let around_ccw = to_touched_site.perp_ccw().norm();
+        let mut candidates = Vec::new();
 
-                let angle_cw = Vector2::angle(to_target_site, around_cw);
-                let angle_ccw = Vector2::angle(to_target_site, around_ccw);
+        for site in context.sites.iter().filter(|site| site.is_tower() && site.tower().owner == Owner::Friendly) {
+            let away = site.position - threat_center;
+            let away = if away.len() > 0.0 { away.norm() } else { Vector2 { x: 1.0, y: 0.0 } };
+            candidates.push(site.position + away.mul(site.radius as f64 + queen_radius + 10.0));
 
-                return if angle_cw < angle_ccw {
-                    QueenCommand::Move(queen_position + around_cw.mul(100.0))
-                } else {
-                    QueenCommand::Move(queen_position + around_ccw.mul(100.0))
-                }
+            // closest covered position
+            let toward_queen = queen_position - site.position;
+
+            if toward_queen.len() > 0.0 {
+                candidates.push(site.position + toward_queen.norm().mul(site.tower().attack_radius as f64 - queen_radius));
             }
         }
 
-        QueenCommand::Move(target_site.position)
+        for index in 0..16 {
+            let angle = index as f64 * std::f64::consts::TAU / 16.0;
+            candidates.push(queen_position + Vector2 { x: angle.cos(), y: angle.sin() }.mul(3.0 * queen_speed));
+        }
+
+        let score = |position: Vector2| {
+            let travel = (position - queen_position).len();
+            let arrival_turns = (travel / queen_speed).ceil() as usize;
+
+            let knight_dist = predictor.units_after(arrival_turns).iter()
+                .filter(|unit| unit.unit_type == UnitType::Knight)
+                .map(|unit| (unit.position - position).len())
+                .fold(COVER_SCORE, f64::min);
+
+            let mut score = context.towers_in_range(position, Owner::Friendly) as f64 * COVER_SCORE + knight_dist - travel / 2.0;
+
+            if context.towers_in_range(position, Owner::Enemy) > 0 {
+                score -= ENEMY_TOWER_PENALTY;
+            }
+
+            score
+        };
+
+        candidates.into_iter()
+            .map(|position| Vector2 {
+                x: position.x.clamp(queen_radius, MAP_WIDTH - queen_radius),
+                y: position.y.clamp(queen_radius, MAP_HEIGHT - queen_radius),
+            })
+            .max_by(|a, b| score(*a).total_cmp(&score(*b)))
+            .unwrap()
+    }
+
+    /// Safe position if it is covered by a friendly tower and out of range of enemy towers, None otherwise.
+    pub fn covered_position(&self, context: &Context) -> Option<Vector2> {
+        let position = self.safe_position(context);
+
+        let is_covered = context.towers_in_range(position, Owner::Friendly) > 0
+            && context.towers_in_range(position, Owner::Enemy) == 0;
+
+        is_covered.then_some(position)
+    }
+}
+
+impl Task for FleeTask {
+    fn is_task_done(&self, context: &Context) -> bool {
+        // once covered, the strategy may continue building within the range of the towers
+        !FleeTask::is_needed(context, self.knight_danger_turns)
+            || context.towers_in_range(context.friendly_queen().position, Owner::Friendly) > 0
+    }
+
+    fn get_next_command(&self, context: &Context) -> QueenCommand {
+        let queen_position = context.friendly_queen().position;
+        let planner = PathPlanner::new(context, -1);
+
+        QueenCommand::Move(planner.next_target(queen_position, self.safe_position(context), UnitType::Queen.speed()))
+    }
+
+    fn priority(&self) -> TaskPriority {
+        TaskPriority::Critical
+    }
+}
+
+impl TowerUpkeepTask {
+    pub fn new(site: &Site, radius_goal: i32) -> TowerUpkeepTask {
+        TowerUpkeepTask {
+            site_id: site.id,
+            radius_goal,
+        }
+    }
+}
+
+impl Task for TowerUpkeepTask {
+    fn is_task_done(&self, context: &Context) -> bool {
+        let site = context.site_by_id(self.site_id);
+
+        match &site.structure {
+            Structure::Tower(tower) => {
+                tower.attack_radius > self.radius_goal || tower.owner != Owner::Friendly
+            },
+            _ => true,  // abort
+        }
+    }
+
+    fn get_next_command(&self, context: &Context) -> QueenCommand {
+        if context.touched_site_id == self.site_id {
+            return QueenCommand::Build(BuildCommand {
+                build_type: BuildType::Tower,
+                site_id: self.site_id,
+            });
+        }
+
+        let site = context.site_by_id(self.site_id);
+        let queen_position = context.friendly_queen().position;
+        let planner = PathPlanner::new(context, self.site_id);
+
+        QueenCommand::Move(planner.next_target(queen_position, site.position, UnitType::Queen.speed()))
     }
 }
 
 impl WaitTask {
-    fn new() -> WaitTask {
+    pub fn new() -> WaitTask {
         WaitTask {}
     }
 }
 
+impl Default for WaitTask {
+    fn default() -> Self {
+        WaitTask::new()
+    }
+}
+
 impl Task for WaitTask {
     fn is_task_done(&self, _context: &Context) -> bool {
         true
@@ -192,3 +428,44 @@ impl Task for WaitTask {
     }
 }
 
+impl CommandTask {
+    pub fn new(context: &Context, command: QueenCommand) -> CommandTask {
+        CommandTask {
+            command,
+            turn: context.history.turn_count(),
+        }
+    }
+}
+
+impl Task for CommandTask {
+    fn is_task_done(&self, context: &Context) -> bool {
+        context.history.turn_count() > self.turn
+    }
+
+    fn get_next_command(&self, _context: &Context) -> QueenCommand {
+        self.command.clone()
+    }
+}
+
+impl PriorityTask {
+    pub fn new(task: Box<dyn Task>, priority: TaskPriority) -> PriorityTask {
+        PriorityTask {
+            task,
+            priority,
+        }
+    }
+}
+
+impl Task for PriorityTask {
+    fn is_task_done(&self, context: &Context) -> bool {
+        self.task.is_task_done(context)
+    }
+
+    fn get_next_command(&self, context: &Context) -> QueenCommand {
+        self.task.get_next_command(context)
+    }
+
+    fn priority(&self) -> TaskPriority {
+        self.priority
+    }
+}
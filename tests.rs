@@ -0,0 +1,1710 @@
+use std::path::Path;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::behavior::{BehaviorTree, Condition, Inverter, Node, Selector, Sequence, Status, TaskAction};
+use crate::bot::Bot;
+use crate::bundler::{bundle, compact_whitespace, strip_comments, MAX_SUBMISSION_CHARS};
+use crate::command::{BuildCommand, BuildType, QueenCommand, TrainCommand};
+use crate::context::Context;
+use crate::economy::{mine_yield, plan_mine, MAX_TURNS};
+use crate::engine::Game;
+use crate::history::EventKind;
+use crate::influence::InfluenceMap;
+use crate::owner::Owner;
+use crate::params::StrategyParams;
+use crate::path::PathPlanner;
+use crate::plan::{plan_tour, Plan};
+use crate::point::Vector2;
+use crate::protocol::{parse_init, parse_turn, ProtocolError};
+use crate::referee::{play_game, BotProcess, Player, PlayerSpec};
+use crate::replay::replay_log;
+use crate::search::{MacroAction, MonteCarloSearch};
+use crate::site::{tower_attack_radius, BarracksStructure, BarracksType, MineStructure, Site, Structure, TowerStructure, TOWER_MELT_RATE};
+use crate::strategy::{BehaviorTreeStrategy, DefaultStrategy, RushStrategy, SearchStrategy, Strategy};
+use crate::task::{
+    BuildBarracksTask, BuildMineTask, BuildTowerTask, FleeTask, MoveToBuildSiteTask, PriorityTask, Task, TaskPriority,
+    TowerUpkeepTask, WaitTask,
+};
+use crate::symmetry::{mirror, Symmetry};
+use crate::threat::ThreatPredictor;
+use crate::timer::{TurnTimer, FIRST_TURN_TIME};
+use crate::unit::{Unit, UnitType, ARCHER_DAMAGE, ARCHER_DAMAGE_TO_GIANTS, GIANT_BUST_RATE, KNIGHT_DAMAGE};
+
+/// Builds a context as the bot would see it after parsing a turn.
+struct ContextBuilder {
+    context: Context,
+    start_position: Option<Vector2>,
+}
+
+impl ContextBuilder {
+    fn new() -> ContextBuilder {
+        ContextBuilder {
+            context: Context::new(),
+            start_position: None,
+        }
+    }
+
+    fn site(mut self, id: i32, x: f64, y: f64, structure: Structure) -> ContextBuilder {
+        self.context.sites.push(Site {
+            id,
+            position: Vector2 { x, y },
+            radius: 60,
+            gold: 200,
+            max_mining_rate: 3,
+            resources_visible: true,
+            structure,
+        });
+        self
+    }
+
+    fn empty_site(self, id: i32, x: f64, y: f64) -> ContextBuilder {
+        self.site(id, x, y, Structure::NoStructure)
+    }
+
+    fn mine(self, id: i32, x: f64, y: f64, owner: Owner, mining_rate: i32) -> ContextBuilder {
+        self.site(id, x, y, Structure::Mine(MineStructure { owner, mining_rate }))
+    }
+
+    fn tower(self, id: i32, x: f64, y: f64, owner: Owner, attack_radius: i32) -> ContextBuilder {
+        self.site(id, x, y, Structure::Tower(TowerStructure { owner, health: 400, attack_radius }))
+    }
+
+    fn barracks(self, id: i32, x: f64, y: f64, owner: Owner, barracks_type: BarracksType) -> ContextBuilder {
+        self.site(id, x, y, Structure::Barracks(BarracksStructure { owner, barracks_type, turns_to_train: 0 }))
+    }
+
+    fn unit(mut self, unit_type: UnitType, owner: Owner, x: f64, y: f64) -> ContextBuilder {
+        self.context.units.push(Unit {
+            position: Vector2 { x, y },
+            owner,
+            unit_type,
+            health: unit_type.max_health(),
+        });
+        self
+    }
+
+    fn queen(self, x: f64, y: f64) -> ContextBuilder {
+        self.unit(UnitType::Queen, Owner::Friendly, x, y)
+    }
+
+    fn enemy_knight(self, x: f64, y: f64) -> ContextBuilder {
+        self.unit(UnitType::Knight, Owner::Enemy, x, y)
+    }
+
+    fn gold(mut self, gold: i32) -> ContextBuilder {
+        self.context.gold = gold;
+        self
+    }
+
+    fn touching(mut self, site_id: i32) -> ContextBuilder {
+        self.context.touched_site_id = site_id;
+        self
+    }
+
+    fn start_position(mut self, x: f64, y: f64) -> ContextBuilder {
+        self.start_position = Some(Vector2 { x, y });
+        self
+    }
+
+    fn build(mut self) -> Context {
+        // like on the first turn, the start position defaults to the position of the queen
+        self.context.start_position = self.start_position.unwrap_or_else(|| self.context.friendly_queen().position);
+        self.context.enemy_start_position = mirror(self.context.start_position);
+        self.context.symmetry = Symmetry::new(&self.context.sites);
+        self.context
+    }
+}
+
+fn next_command(context: &Context) -> String {
+    DefaultStrategy::new(StrategyParams::default()).get_next_task(context).get_next_command(context).to_string()
+}
+
+#[test]
+fn builds_mine_on_touched_site_without_mines() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .empty_site(1, 500.0, 500.0)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 MINE");
+}
+
+#[test]
+fn moves_to_closest_site_if_not_touching() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 500.0, 500.0)
+        .empty_site(1, 300.0, 100.0)
+        .build();
+
+    assert_eq!(next_command(&context), "MOVE 300 100");
+}
+
+#[test]
+fn waits_without_site_to_build() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 1800.0, 900.0)
+        .build();
+
+    assert_eq!(next_command(&context), "WAIT");
+}
+
+#[test]
+fn builds_tower_when_knight_rush_is_incoming() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .enemy_knight(600.0, 100.0)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 TOWER");
+}
+
+#[test]
+fn skips_mine_when_knight_is_close() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .tower(1, 100.0, 300.0, Owner::Friendly, 300)
+        .enemy_knight(250.0, 100.0)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn builds_knight_barracks_after_minimum_mines() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .mine(1, 100.0, 300.0, Owner::Friendly, 1)
+        .mine(2, 300.0, 300.0, Owner::Friendly, 1)
+        .mine(3, 500.0, 300.0, Owner::Friendly, 1)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn builds_giant_barracks_when_enemy_has_towers() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .mine(1, 100.0, 300.0, Owner::Friendly, 1)
+        .mine(2, 300.0, 300.0, Owner::Friendly, 1)
+        .mine(3, 500.0, 300.0, Owner::Friendly, 1)
+        .barracks(4, 700.0, 300.0, Owner::Friendly, BarracksType::Knight)
+        .tower(5, 100.0, 500.0, Owner::Friendly, 300)
+        .tower(6, 300.0, 500.0, Owner::Friendly, 300)
+        .tower(7, 1800.0, 900.0, Owner::Enemy, 300)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 BARRACKS-GIANT");
+}
+
+#[test]
+fn trains_nothing_without_barracks() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_next_unit_to_train(&context), None);
+}
+
+#[test]
+fn trains_knights_by_default() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 300.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 500.0, 100.0, Owner::Friendly, BarracksType::Giant)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_next_unit_to_train(&context), Some(BarracksType::Knight));
+}
+
+#[test]
+fn trains_giant_against_enemy_towers() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 300.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 500.0, 100.0, Owner::Friendly, BarracksType::Giant)
+        .tower(2, 1800.0, 900.0, Owner::Enemy, 300)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_next_unit_to_train(&context), Some(BarracksType::Giant));
+}
+
+#[test]
+fn trains_only_one_giant_at_a_time() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 300.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 500.0, 100.0, Owner::Friendly, BarracksType::Giant)
+        .tower(2, 1800.0, 900.0, Owner::Enemy, 300)
+        .unit(UnitType::Giant, Owner::Friendly, 700.0, 500.0)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_next_unit_to_train(&context), Some(BarracksType::Knight));
+}
+
+#[test]
+fn train_command_is_limited_by_gold() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 300.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 500.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .gold(100)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_train_command(&context).to_string(), "TRAIN 0");
+}
+
+#[test]
+fn closest_site_to_build_avoids_enemy_towers() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 100.0)
+        .empty_site(1, 100.0, 500.0)
+        .tower(2, 400.0, 100.0, Owner::Enemy, 200)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_closest_site_to_build(&context).map(|site| site.id), Some(1));
+}
+
+#[test]
+fn closest_site_to_build_stays_near_start() {
+    let context = ContextBuilder::new()
+        .queen(900.0, 500.0)
+        .empty_site(0, 1200.0, 500.0)
+        .empty_site(1, 500.0, 500.0)
+        .start_position(100.0, 500.0)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_closest_site_to_build(&context).map(|site| site.id), Some(1));
+}
+
+#[test]
+fn closest_site_to_build_skips_own_structures() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 200.0, 100.0, Owner::Friendly, 1)
+        .mine(1, 300.0, 100.0, Owner::Enemy, 1)
+        .build();
+
+    assert_eq!(DefaultStrategy::new(StrategyParams::default()).get_closest_site_to_build(&context).map(|site| site.id), Some(1));
+}
+
+#[test]
+fn build_mine_task_is_done_at_max_mining_rate() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 190.0, 100.0, Owner::Friendly, 2)
+        .mine(1, 100.0, 300.0, Owner::Friendly, 3)
+        .build();
+
+    assert!(!BuildMineTask { site_id: 0, knight_danger_turns: 3, rate_goal: 3 }.is_task_done(&context));
+    assert!(BuildMineTask { site_id: 1, knight_danger_turns: 3, rate_goal: 3 }.is_task_done(&context));
+}
+
+#[test]
+fn build_mine_task_aborts_on_knight_rush() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 190.0, 100.0, Owner::Friendly, 1)
+        .enemy_knight(300.0, 100.0)
+        .build();
+
+    assert!(BuildMineTask { site_id: 0, knight_danger_turns: 3, rate_goal: 3 }.is_task_done(&context));
+}
+
+#[test]
+fn build_tower_task_is_done_above_radius() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .tower(0, 190.0, 100.0, Owner::Friendly, 300)
+        .tower(1, 500.0, 100.0, Owner::Friendly, 400)
+        .build();
+
+    assert!(!BuildTowerTask { site_id: 0, radius_goal: 350 }.is_task_done(&context));
+    assert!(BuildTowerTask { site_id: 1, radius_goal: 350 }.is_task_done(&context));
+}
+
+#[test]
+fn build_barracks_task_is_done_when_built() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 190.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .build();
+
+    assert!(BuildBarracksTask { site_id: 0, barracks_type: BarracksType::Knight }.is_task_done(&context));
+    assert!(!BuildBarracksTask { site_id: 0, barracks_type: BarracksType::Giant }.is_task_done(&context));
+}
+
+#[test]
+fn move_task_is_done_close_to_site() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 230.0, 100.0)
+        .empty_site(1, 600.0, 100.0)
+        .build();
+
+    assert!(MoveToBuildSiteTask { site_id: 0, knight_danger_turns: 3 }.is_task_done(&context));
+    assert!(!MoveToBuildSiteTask { site_id: 1, knight_danger_turns: 3 }.is_task_done(&context));
+}
+
+#[test]
+fn wait_task_is_always_done() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .build();
+
+    assert!(WaitTask {}.is_task_done(&context));
+}
+
+#[test]
+fn rush_strategy_builds_knight_barracks_first() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .touching(0)
+        .build();
+
+    let strategy = RushStrategy::new(StrategyParams::default());
+    assert_eq!(strategy.get_next_task(&context).get_next_command(&context).to_string(), "BUILD 0 BARRACKS-KNIGHT");
+    assert!(strategy.get_next_strategy(&context).is_none());
+}
+
+#[test]
+fn rush_strategy_switches_to_default_after_barracks() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 190.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .build();
+
+    assert!(RushStrategy::new(StrategyParams::default()).get_next_strategy(&context).is_some());
+}
+
+#[test]
+fn params_are_read_from_key_values() {
+    let mut params = StrategyParams::default();
+    params.apply_key_values("# tuned\nmin_mine_count = 4\n\nbuild_radius=850.5\n").unwrap();
+
+    assert_eq!(params.min_mine_count, 4);
+    assert_eq!(params.build_radius, 850.5);
+    assert_eq!(params.max_mine_count, StrategyParams::default().max_mine_count);
+}
+
+#[test]
+fn params_reject_unknown_keys() {
+    let mut params = StrategyParams::default();
+
+    assert!(params.apply_key_values("mine_count=4").is_err());
+    assert!(params.apply_key_values("min_mine_count=many").is_err());
+}
+
+#[test]
+fn params_round_trip_through_key_values() {
+    let mut params = StrategyParams::default();
+    params.set("build_radius", 800.0).unwrap();
+
+    let mut loaded = StrategyParams::default();
+    loaded.apply_key_values(&params.to_key_values()).unwrap();
+
+    assert_eq!(loaded, params);
+}
+
+#[test]
+fn strategy_uses_mine_count_param() {
+    let params = StrategyParams {
+        min_mine_count: 1,
+        ..StrategyParams::default()
+    };
+
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .mine(1, 100.0, 300.0, Owner::Friendly, 1)
+        .touching(0)
+        .build();
+
+    let command = DefaultStrategy::new(params).get_next_task(&context).get_next_command(&context);
+    assert_eq!(command.to_string(), "BUILD 0 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn predicts_turns_until_knight_reaches_queen() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .enemy_knight(600.0, 100.0)
+        .build();
+
+    let predictor = ThreatPredictor::new(&context, 10);
+    assert_eq!(predictor.turns_until_knight_reaches_queen(&context), Some(5));
+}
+
+#[test]
+fn predicts_no_knight_threat_beyond_horizon() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .enemy_knight(1800.0, 100.0)
+        .build();
+
+    let predictor = ThreatPredictor::new(&context, 10);
+    assert_eq!(predictor.turns_until_knight_reaches_queen(&context), None);
+}
+
+#[test]
+fn predicts_giants_walking_to_closest_tower() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .tower(0, 500.0, 500.0, Owner::Friendly, 300)
+        .tower(1, 1000.0, 500.0, Owner::Friendly, 300)
+        .unit(UnitType::Giant, Owner::Enemy, 1500.0, 500.0)
+        .build();
+
+    let predictor = ThreatPredictor::new(&context, 10);
+    let giant = predictor.units_after(4)[0];
+
+    assert_eq!(giant.position.x, 1300.0);
+    assert_eq!(predictor.units_after(100)[0].position.x, 1100.0);
+}
+
+#[test]
+fn finds_straight_path_without_obstacles() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .empty_site(0, 800.0, 500.0)
+        .empty_site(1, 400.0, 900.0)
+        .build();
+
+    let path = PathPlanner::new(&context, 0).find_path(Vector2 { x: 100.0, y: 500.0 }, Vector2 { x: 800.0, y: 500.0 });
+    assert_eq!(path.len(), 1);
+    assert_eq!(path[0].x, 800.0);
+}
+
+#[test]
+fn finds_path_around_site_in_the_way() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .empty_site(0, 800.0, 500.0)
+        .empty_site(1, 450.0, 500.0)
+        .build();
+
+    let start = Vector2 { x: 100.0, y: 500.0 };
+    let path = PathPlanner::new(&context, 0).find_path(start, Vector2 { x: 800.0, y: 500.0 });
+    assert!(path.len() > 1);
+
+    let mut from = start;
+
+    for waypoint in &path {
+        // sample the legs, the queen must never overlap the blocking site
+        for step in 0..=20 {
+            let point = from + (*waypoint - from).mul(step as f64 / 20.0);
+            assert!((point - Vector2 { x: 450.0, y: 500.0 }).len() >= 60.0 + 30.0 - 0.01);
+        }
+
+        from = *waypoint;
+    }
+}
+
+#[test]
+fn moves_around_enemy_tower_range() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .empty_site(0, 900.0, 500.0)
+        .tower(1, 500.0, 500.0, Owner::Enemy, 250)
+        .build();
+
+    match MoveToBuildSiteTask::new(0, 3).get_next_command(&context) {
+        QueenCommand::Move(target) => {
+            // a full step, turning away from the straight line through the tower range
+            assert!(((target - Vector2 { x: 100.0, y: 500.0 }).len() - 60.0).abs() < 0.01);
+            assert!((target.y - 500.0).abs() > 20.0);
+        },
+        command => panic!("unexpected command {}", command),
+    }
+}
+
+#[test]
+fn flees_behind_tower_from_knights() {
+    let context = ContextBuilder::new()
+        .queen(500.0, 500.0)
+        .empty_site(0, 500.0, 800.0)
+        .tower(1, 300.0, 500.0, Owner::Friendly, 300)
+        .enemy_knight(800.0, 500.0)
+        .build();
+
+    assert!(FleeTask::is_needed(&context, 3));
+
+    // behind the tower as seen from the knight
+    let safe_position = FleeTask::new(3).safe_position(&context);
+    assert!(safe_position.x < 300.0);
+    assert!((safe_position - Vector2 { x: 300.0, y: 500.0 }).len() < 300.0);
+
+    assert!(next_command(&context).starts_with("MOVE"));
+}
+
+#[test]
+fn does_not_flee_without_close_tower() {
+    let context = ContextBuilder::new()
+        .queen(500.0, 500.0)
+        .empty_site(0, 590.0, 500.0)
+        .tower(1, 1700.0, 900.0, Owner::Friendly, 300)
+        .enemy_knight(800.0, 500.0)
+        .touching(0)
+        .build();
+
+    assert!(!FleeTask::is_needed(&context, 3));
+}
+
+#[test]
+fn flee_task_is_done_when_knights_are_gone() {
+    let context = ContextBuilder::new()
+        .queen(200.0, 500.0)
+        .tower(1, 300.0, 500.0, Owner::Friendly, 300)
+        .enemy_knight(1800.0, 500.0)
+        .build();
+
+    assert!(FleeTask::new(3).is_task_done(&context));
+}
+
+#[test]
+fn builds_within_tower_range_when_knights_close_in() {
+    let context = ContextBuilder::new()
+        .queen(400.0, 500.0)
+        .empty_site(0, 490.0, 500.0)
+        .empty_site(2, 400.0, 900.0)
+        .tower(1, 300.0, 500.0, Owner::Friendly, 300)
+        .enemy_knight(650.0, 500.0)
+        .touching(0)
+        .build();
+
+    assert!(FleeTask::is_needed(&context, 3));
+    assert!(FleeTask::new(3).is_task_done(&context));
+    assert_eq!(next_command(&context), "BUILD 0 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn history_records_structures_built_and_lost() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 500.0, 500.0)
+        .mine(1, 900.0, 500.0, Owner::Enemy, 2)
+        .build();
+
+    context.record_turn();
+    context.site_by_id_mut(0).structure = Structure::Tower(TowerStructure { owner: Owner::Friendly, health: 200, attack_radius: 200 });
+    context.site_by_id_mut(1).structure = Structure::NoStructure;
+    context.record_turn();
+
+    let events: Vec<(i32, EventKind)> = context.history.events_since(1).map(|event| (event.site_id, event.kind)).collect();
+
+    assert_eq!(events.len(), 2);
+    assert!(events.contains(&(0, EventKind::StructureBuilt(BuildType::Tower))));
+    assert!(events.contains(&(1, EventKind::StructureLost(BuildType::Mine))));
+}
+
+#[test]
+fn history_records_training_and_spawns() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 1500.0, 500.0, Owner::Enemy, BarracksType::Knight)
+        .build();
+
+    let set_turns_to_train = |context: &mut Context, turns: i32| {
+        if let Structure::Barracks(barracks) = &mut context.site_by_id_mut(0).structure {
+            barracks.turns_to_train = turns;
+        }
+    };
+
+    context.record_turn();
+
+    for turns in [4, 3, 2, 1, 0] {
+        set_turns_to_train(&mut context, turns);
+        context.record_turn();
+
+        if turns == 2 {
+            assert_eq!(context.history.training_turns(0), Some(2));
+        }
+    }
+
+    let kinds: Vec<EventKind> = context.history.events.iter().map(|event| event.kind).collect();
+
+    assert!(kinds == [
+        EventKind::TrainingStarted(BarracksType::Knight),
+        EventKind::UnitsSpawned(UnitType::Knight, 4),
+    ]);
+    assert_eq!(context.history.events[0].turn, 1);
+    assert_eq!(context.history.training_turns(0), None);
+}
+
+#[test]
+fn history_estimates_enemy_income() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 1500.0, 500.0, Owner::Enemy, 3)
+        .mine(1, 1500.0, 800.0, Owner::Enemy, 2)
+        .mine(2, 300.0, 800.0, Owner::Friendly, 1)
+        .build();
+
+    context.site_by_id_mut(1).gold = 1;
+    context.record_turn();
+
+    assert_eq!(context.history.income(Owner::Enemy, |site| site.mine().mining_rate), 4);
+    assert_eq!(context.history.income(Owner::Friendly, |site| site.mine().mining_rate), 1);
+}
+
+#[test]
+fn history_estimates_income_of_enemy_mines_with_unknown_rate() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 1500.0, 500.0, Owner::Enemy, -1)
+        .mine(1, 1500.0, 800.0, Owner::Enemy, -1)
+        .build();
+
+    context.site_by_id_mut(0).max_mining_rate = 2;
+    context.site_by_id_mut(1).gold = -1;
+    context.site_by_id_mut(1).max_mining_rate = -1;
+    context.record_turn();
+
+    let income = context.history.income(Owner::Enemy, |site| context.estimated_mining_rate(site));
+
+    // the second mine is out of sight and assumed to mine at the prior maximum rate of 2
+    assert_eq!(income, 4);
+}
+
+#[test]
+fn estimates_enemy_gold_from_mines_and_training() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 1500.0, 500.0, Owner::Enemy, 3)
+        .barracks(1, 1500.0, 800.0, Owner::Enemy, BarracksType::Knight)
+        .build();
+
+    context.record_turn();
+    context.record_turn();
+    assert_eq!((context.enemy_gold.lower, context.enemy_gold.upper), (103, 103));
+
+    if let Structure::Barracks(barracks) = &mut context.site_by_id_mut(1).structure {
+        barracks.turns_to_train = 4;
+    }
+
+    context.site_by_id_mut(0).structure = Structure::NoStructure;
+    context.record_turn();
+
+    // the mine may have been exhausted before producing
+    assert_eq!((context.enemy_gold.lower, context.enemy_gold.upper), (23, 26));
+    assert_eq!(context.enemy_gold.turns_until_affordable(80, 0), None);
+    assert_eq!(context.enemy_gold.turns_until_affordable(80, 5), Some(11));
+}
+
+#[test]
+fn estimates_enemy_gold_from_mines_with_unknown_rate() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 1500.0, 500.0, Owner::Enemy, -1)
+        .mine(1, 1500.0, 800.0, Owner::Enemy, -1)
+        .build();
+
+    context.site_by_id_mut(1).max_mining_rate = 1;
+    context.record_turn();
+    context.record_turn();
+
+    // each mine yields at least 1 and at most its maximum rate
+    assert_eq!((context.enemy_gold.lower, context.enemy_gold.upper), (102, 104));
+
+    context.site_by_id_mut(0).structure = Structure::NoStructure;
+    context.record_turn();
+
+    assert_eq!((context.enemy_gold.lower, context.enemy_gold.upper), (103, 108));
+}
+
+#[test]
+fn builds_tower_before_knight_rush_lands() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .barracks(1, 500.0, 100.0, Owner::Enemy, BarracksType::Knight)
+        .touching(0)
+        .build();
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+
+    assert_eq!(strategy.turns_until_knight_rush(&context), Some(9));
+    assert_eq!(next_command(&context), "BUILD 0 TOWER");
+}
+
+#[test]
+fn builds_archer_barracks_when_knights_mass_near_base() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .mine(1, 100.0, 300.0, Owner::Friendly, 1)
+        .mine(2, 300.0, 300.0, Owner::Friendly, 1)
+        .mine(3, 500.0, 300.0, Owner::Friendly, 1)
+        .barracks(4, 100.0, 500.0, Owner::Friendly, BarracksType::Knight)
+        .tower(5, 300.0, 700.0, Owner::Friendly, 200)
+        .enemy_knight(700.0, 600.0)
+        .enemy_knight(700.0, 650.0)
+        .enemy_knight(750.0, 600.0)
+        .enemy_knight(750.0, 650.0)
+        .touching(0)
+        .build();
+
+    assert_eq!(next_command(&context), "BUILD 0 BARRACKS-ARCHER");
+}
+
+#[test]
+fn trains_archers_against_massing_knights() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .barracks(0, 190.0, 100.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 100.0, 300.0, Owner::Friendly, BarracksType::Archer)
+        .enemy_knight(700.0, 600.0)
+        .enemy_knight(700.0, 650.0)
+        .enemy_knight(750.0, 600.0)
+        .enemy_knight(750.0, 650.0)
+        .unit(UnitType::Archer, Owner::Friendly, 300.0, 300.0)
+        .build();
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+    assert_eq!(strategy.get_next_unit_to_train(&context), Some(BarracksType::Archer));
+}
+
+#[test]
+fn predicts_friendly_archers_shooting_knights() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .enemy_knight(1200.0, 100.0)
+        .unit(UnitType::Archer, Owner::Friendly, 900.0, 100.0)
+        .build();
+
+    context.units[1].health = 4;
+
+    let predictor = ThreatPredictor::new(&context, 10);
+
+    assert_eq!(predictor.units_after(0).len(), 1);
+    assert_eq!(predictor.units_after(1)[0].health, 1);
+    assert!(predictor.units_after(2).is_empty());
+}
+
+#[test]
+fn tower_health_matches_attack_radius() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .tower(0, 500.0, 500.0, Owner::Friendly, 300)
+        .build();
+
+    let site = context.site_by_id(0);
+
+    // 400 health on a site of radius 60 covers a radius of about 361.8
+    assert_eq!(site.tower_health_for_radius(361), 399);
+    assert_eq!(site.tower_health_for_radius(362), 401);
+    assert_eq!(site.tower_turns_above_radius(250), 53);
+    assert_eq!(site.tower_turns_above_radius(400), 0);
+}
+
+#[test]
+fn tops_up_close_melting_tower() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .tower(1, 300.0, 300.0, Owner::Friendly, 260)
+        .tower(2, 1500.0, 900.0, Owner::Friendly, 260)
+        .touching(0)
+        .build();
+
+    for site_id in [1, 2] {
+        if let Structure::Tower(tower) = &mut context.site_by_id_mut(site_id).structure {
+            tower.health = 200;
+        }
+    }
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+    assert_eq!(strategy.get_tower_to_upkeep(&context).map(|site| site.id), Some(1));
+
+    let task = TowerUpkeepTask::new(context.site_by_id(1), 350);
+    assert!(!task.is_task_done(&context));
+    assert!(task.get_next_command(&context).to_string().starts_with("MOVE"));
+
+    context.touched_site_id = 1;
+    assert_eq!(task.get_next_command(&context).to_string(), "BUILD 1 TOWER");
+}
+
+#[test]
+fn leaves_healthy_towers_alone() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .tower(1, 300.0, 300.0, Owner::Friendly, 371)
+        .build();
+
+    assert!(DefaultStrategy::new(StrategyParams::default()).get_tower_to_upkeep(&context).is_none());
+}
+
+#[test]
+fn plans_mine_upgrades_by_remaining_gold() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .empty_site(1, 100.0, 300.0)
+        .build();
+
+    context.site_by_id_mut(0).gold = 300;
+    context.site_by_id_mut(1).gold = 12;
+
+    // rich sites are upgraded to the maximum, nearly exhausted ones are not worth the turns
+    assert_eq!(plan_mine(&context, context.site_by_id(0), 0, 200, 10.0).rate, 3);
+    assert_eq!(plan_mine(&context, context.site_by_id(1), 0, 200, 10.0).rate, 1);
+
+    // no time left to mine
+    assert!(plan_mine(&context, context.site_by_id(0), 5, 5, 10.0).value <= 0.0);
+    // all 12 gold mined within five turns, slightly discounted
+    assert!((11.0..12.0).contains(&mine_yield(&context, context.site_by_id(1), 3, 0, 200)));
+}
+
+#[test]
+fn walks_to_richer_mine_site() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 190.0, 100.0)
+        .empty_site(1, 100.0, 300.0)
+        .touching(0)
+        .build();
+
+    context.site_by_id_mut(0).gold = 10;
+    context.site_by_id_mut(0).max_mining_rate = 1;
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+    assert_eq!(strategy.get_best_mine_plan(&context, &InfluenceMap::new(&context)).map(|plan| plan.site_id), Some(1));
+    assert_eq!(next_command(&context), "MOVE 100 300");
+}
+
+#[test]
+fn upgrades_friendly_mine_before_building_poor_one() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .mine(0, 190.0, 100.0, Owner::Friendly, 1)
+        .empty_site(1, 100.0, 300.0)
+        .touching(0)
+        .build();
+
+    context.site_by_id_mut(0).gold = 300;
+    context.site_by_id_mut(1).gold = 100;
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+    let plan = strategy.get_best_mine_plan(&context, &InfluenceMap::new(&context)).unwrap();
+    assert_eq!((plan.site_id, plan.rate), (0, 3));
+    assert_eq!(next_command(&context), "BUILD 0 MINE");
+
+    // nothing left to upgrade at the maximum rate
+    context.site_by_id_mut(0).structure = Structure::Mine(MineStructure { owner: Owner::Friendly, mining_rate: 3 });
+    assert_eq!(strategy.get_best_mine_plan(&context, &InfluenceMap::new(&context)).map(|plan| plan.site_id), Some(1));
+}
+
+#[test]
+fn keeps_last_seen_site_resources() {
+    let init = "2\n0 300 300 60\n1 1620 700 60\n";
+    let mut context = parse_init(&mut init.as_bytes()).unwrap();
+
+    let seen = "100 -1\n0 230 3 -1 -1 -1 -1\n1 -1 -1 -1 -1 -1 -1\n1\n300 300 0 -1 100\n";
+    let hidden = "100 -1\n0 -1 -1 0 1 2 -1\n1 -1 -1 -1 -1 -1 -1\n1\n1620 700 0 -1 100\n";
+    assert!(parse_turn(&mut seen.as_bytes(), &mut context).unwrap());
+    assert!(parse_turn(&mut hidden.as_bytes(), &mut context).unwrap());
+
+    // the enemy mine keeps extracting while out of sight
+    let site = context.site_by_id(0);
+    assert!(!site.resources_visible);
+    assert_eq!((site.gold, site.max_mining_rate), (228, 3));
+    assert_eq!(context.site_by_id(1).gold, -1);
+}
+
+#[test]
+fn estimates_unseen_site_resources_from_mirrored_site() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .empty_site(1, 1620.0, 700.0)
+        .empty_site(2, 900.0, 200.0)
+        .build();
+
+    for site_id in [1, 2] {
+        let site = context.site_by_id_mut(site_id);
+        site.gold = -1;
+        site.max_mining_rate = -1;
+    }
+
+    assert_eq!(context.mirrored_site(context.site_by_id(1)).unwrap().id, 0);
+    assert_eq!(context.estimated_gold(context.site_by_id(1)), 200);
+    assert_eq!(context.estimated_max_mining_rate(context.site_by_id(1)), 3);
+
+    // no mirrored site in the fixture, falls back to the prior
+    assert_eq!(context.estimated_max_mining_rate(context.site_by_id(2)), 2);
+}
+
+#[test]
+fn estimates_gold_of_sites_out_of_sight() {
+    let init = "3\n0 300 300 60\n1 1620 700 60\n2 300 700 60\n";
+    let mut context = parse_init(&mut init.as_bytes()).unwrap();
+
+    let seen = "100 -1\n0 200 3 -1 -1 -1 -1\n1 -1 -1 -1 -1 -1 -1\n2 180 2 0 1 -1 -1\n1\n300 300 0 -1 100\n";
+    let hidden = "100 -1\n0 -1 -1 0 1 -1 -1\n1 -1 -1 -1 -1 -1 -1\n2 -1 -1 0 1 -1 -1\n1\n1620 700 0 -1 100\n";
+    assert!(parse_turn(&mut seen.as_bytes(), &mut context).unwrap());
+
+    for _ in 0..10 {
+        assert!(parse_turn(&mut hidden.as_bytes(), &mut context).unwrap());
+    }
+
+    // enemy mines of hidden rate out of sight are mined at their maximum rate, without further discount
+    assert_eq!(context.estimated_gold(context.site_by_id(0)), 170);
+    assert_eq!(context.estimated_gold(context.site_by_id(1)), 170);
+    assert_eq!(context.estimated_gold(context.site_by_id(2)), 160);
+}
+
+#[test]
+fn pairs_sites_by_mirrored_position_and_radius() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .empty_site(1, 1620.0, 700.0)
+        .empty_site(2, 900.0, 200.0)
+        .empty_site(3, 1020.0, 800.0)
+        .build();
+
+    context.site_by_id_mut(3).radius = 70;
+    let symmetry = Symmetry::new(&context.sites);
+
+    assert_eq!(symmetry.mirror_id(0), Some(1));
+    assert_eq!(symmetry.mirror_id(1), Some(0));
+    assert_eq!(symmetry.mirror_id(2), None);
+}
+
+#[test]
+fn splits_territory_between_start_positions() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 900.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 100.0)
+        .build();
+
+    context.detect_start_positions();
+
+    assert!(context.is_friendly_territory(Vector2 { x: 900.0, y: 600.0 }));
+    assert!(!context.is_friendly_territory(Vector2 { x: 1100.0, y: 400.0 }));
+    assert!(context.border_distance(Vector2 { x: 960.0, y: 500.0 }).abs() < 1e-6);
+
+    // the start positions are half their distance away from the border
+    let half_distance = (context.enemy_start_position - context.start_position).len() / 2.0;
+    assert!((context.border_distance(context.start_position) - half_distance).abs() < 1e-6);
+}
+
+#[test]
+fn influence_map_rates_sites_by_safety_and_contestedness() {
+    let context = ContextBuilder::new()
+        .queen(200.0, 500.0)
+        .empty_site(0, 300.0, 500.0)
+        .empty_site(1, 960.0, 500.0)
+        .empty_site(2, 1620.0, 500.0)
+        .enemy_knight(1000.0, 500.0)
+        .build();
+
+    let influence = InfluenceMap::new(&context);
+    let (interior, frontier, enemy_side) = (context.site_by_id(0), context.site_by_id(1), context.site_by_id(2));
+
+    assert!(influence.safety(interior) > 0.0);
+    assert!(influence.safety(enemy_side) < 0.0);
+    assert!(influence.contestedness(frontier) > influence.contestedness(interior));
+    assert!(influence.contestedness(frontier) > influence.contestedness(enemy_side));
+}
+
+#[test]
+fn builds_towers_towards_the_frontier() {
+    let context = ContextBuilder::new()
+        .queen(740.0, 450.0)
+        .empty_site(0, 640.0, 400.0)
+        .empty_site(1, 900.0, 500.0)
+        .enemy_knight(1000.0, 500.0)
+        .start_position(500.0, 500.0)
+        .build();
+
+    // the frontier site is a turn further away
+    let frontier = DefaultStrategy::new(StrategyParams { tower_frontier_value: 100.0, ..StrategyParams::default() });
+    assert_eq!(frontier.get_tower_site(&context, &InfluenceMap::new(&context)).unwrap().id, 1);
+
+    let closest = DefaultStrategy::new(StrategyParams { tower_frontier_value: 0.0, ..StrategyParams::default() });
+    assert_eq!(closest.get_tower_site(&context, &InfluenceMap::new(&context)).unwrap().id, 0);
+}
+
+#[test]
+fn plans_shortest_tour_over_sites() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .empty_site(0, 900.0, 500.0)
+        .empty_site(1, 300.0, 500.0)
+        .empty_site(2, 600.0, 500.0)
+        .empty_site(3, 300.0, 900.0)
+        .build();
+
+    let sites: Vec<&Site> = context.sites.iter().collect();
+    let queen_position = context.friendly_queen().position;
+
+    assert_eq!(plan_tour(queen_position, &sites, 3), vec![1, 2, 0]);
+    assert_eq!(plan_tour(queen_position, &sites, 9).len(), 4);
+}
+
+#[test]
+fn plan_is_outdated_by_enemy_structures_only() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .empty_site(1, 1500.0, 700.0)
+        .build();
+
+    context.record_turn();
+    let mut plan = Plan::new(&context, vec![Box::new(BuildTowerTask::new(context.site_by_id(0), 350))]);
+
+    context.site_by_id_mut(0).structure = Structure::Barracks(BarracksStructure {
+        owner: Owner::Friendly,
+        barracks_type: BarracksType::Knight,
+        turns_to_train: 0,
+    });
+    context.record_turn();
+    assert!(!plan.is_outdated(&context));
+    assert!(plan.next_task(&context).is_some());
+
+    context.site_by_id_mut(1).structure = Structure::Tower(TowerStructure { owner: Owner::Enemy, health: 200, attack_radius: 300 });
+    context.record_turn();
+    assert!(plan.is_outdated(&context));
+}
+
+#[test]
+fn plans_barracks_and_towers_ahead() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .mine(0, 100.0, 300.0, Owner::Friendly, 1)
+        .mine(1, 100.0, 700.0, Owner::Friendly, 1)
+        .mine(2, 300.0, 800.0, Owner::Friendly, 1)
+        .empty_site(3, 600.0, 500.0)
+        .empty_site(4, 300.0, 500.0)
+        .empty_site(5, 900.0, 500.0)
+        .build();
+
+    let tasks = DefaultStrategy::new(StrategyParams::default()).get_next_plan(&context, &TurnTimer::new());
+    let commands: Vec<String> = tasks.iter()
+        .skip(1)
+        .step_by(2)
+        .map(|task| task.get_next_command(&context).to_string())
+        .collect();
+
+    assert_eq!(commands, vec!["BUILD 4 BARRACKS-KNIGHT", "BUILD 3 TOWER", "BUILD 5 TOWER"]);
+
+    let single = DefaultStrategy::new(StrategyParams { plan_length: 1, ..StrategyParams::default() });
+    assert_eq!(single.get_next_plan(&context, &TurnTimer::new()).len(), 1);
+}
+
+#[test]
+fn rebuilds_lost_tower_urgently() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .tower(1, 300.0, 300.0, Owner::Friendly, 300)
+        .build();
+
+    let strategy = DefaultStrategy::new(StrategyParams::default());
+    context.record_turn();
+    assert!(strategy.get_urgent_task(&context).is_none());
+
+    context.site_by_id_mut(1).structure = Structure::NoStructure;
+    context.record_turn();
+
+    let task = strategy.get_urgent_task(&context).unwrap();
+    assert_eq!(task.priority(), TaskPriority::High);
+    assert_eq!(task.get_next_command(&context).to_string(), "BUILD 1 TOWER");
+}
+
+/// Builds barracks on site 0, interrupted by a tower on site 1 whenever there is gold.
+struct InterruptedStrategy;
+
+impl Strategy for InterruptedStrategy {
+    fn get_next_task(&self, context: &Context) -> Box<dyn Task> {
+        Box::new(BuildBarracksTask::new(context.site_by_id(0), BarracksType::Knight))
+    }
+
+    fn get_next_unit_to_train(&self, _context: &Context) -> Option<BarracksType> {
+        None
+    }
+
+    fn get_urgent_task(&self, context: &Context) -> Option<Box<dyn Task>> {
+        let task = BuildTowerTask::new(context.site_by_id(1), 350);
+        (context.gold > 0).then(|| Box::new(PriorityTask::new(Box::new(task), TaskPriority::High)) as Box<dyn Task>)
+    }
+}
+
+#[test]
+fn urgent_task_preempts_and_current_task_resumes() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .empty_site(1, 500.0, 300.0)
+        .build();
+
+    let mut bot = Bot::new(context, Box::new(InterruptedStrategy));
+    assert_eq!(bot.play_turn(&TurnTimer::new()).0.to_string(), "BUILD 0 BARRACKS-KNIGHT");
+
+    bot.context.gold = 100;
+    assert_eq!(bot.play_turn(&TurnTimer::new()).0.to_string(), "BUILD 1 TOWER");
+
+    bot.context.gold = 0;
+    bot.context.site_by_id_mut(1).structure = Structure::Tower(TowerStructure { owner: Owner::Friendly, health: 800, attack_radius: 400 });
+    assert_eq!(bot.play_turn(&TurnTimer::new()).0.to_string(), "BUILD 0 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn behavior_tree_runs_first_applicable_branch() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .build();
+
+    let build = |context: &Context| Some(Box::new(BuildTowerTask::new(context.site_by_id(0), 350)) as Box<dyn Task>);
+    let mut tree = BehaviorTree::new(Box::new(Selector::new("root", vec![
+        Box::new(Sequence::new("never", vec![
+            Box::new(Condition::new(|_| false)),
+            Box::new(TaskAction::new("wait", |_| Some(Box::new(WaitTask::new()) as Box<dyn Task>))),
+        ])),
+        Box::new(Sequence::new("defend", vec![
+            Box::new(Inverter::new(Box::new(Condition::new(|context| context.gold > 0)))),
+            Box::new(TaskAction::new("build tower", build)),
+        ])),
+    ])));
+
+    assert_eq!(tree.tick(&context).to_string(), "BUILD 0 TOWER");
+    assert_eq!(tree.active_path(), "root/defend/build tower");
+}
+
+#[test]
+fn task_action_succeeds_once_task_is_done() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 300.0, 300.0)
+        .build();
+
+    let mut action = TaskAction::new("build tower", |context| {
+        Some(Box::new(BuildTowerTask::new(context.site_by_id(0), 350)) as Box<dyn Task>)
+    });
+    let mut path = Vec::new();
+
+    assert!(matches!(action.tick(&context, &mut path), Status::Running(_)));
+    assert_eq!(path, vec!["build tower"]);
+
+    context.site_by_id_mut(0).structure = Structure::Tower(TowerStructure { owner: Owner::Friendly, health: 800, attack_radius: 400 });
+    assert!(matches!(action.tick(&context, &mut Vec::new()), Status::Success));
+}
+
+#[test]
+fn behavior_tree_strategy_walks_to_mine_first() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .empty_site(0, 400.0, 100.0)
+        .build();
+
+    let strategy = BehaviorTreeStrategy::new(StrategyParams::default());
+    assert!(strategy.get_next_task(&context).get_next_command(&context).to_string().starts_with("MOVE"));
+    assert_eq!(strategy.get_debug_info().unwrap(), "queen/economy/build mine/walk");
+}
+
+#[test]
+fn behavior_tree_strategy_builds_where_it_walked() {
+    let context_at = |x: f64| {
+        ContextBuilder::new()
+            .queen(x, 500.0)
+            .start_position(700.0, 500.0)
+            .mine(0, 100.0, 200.0, Owner::Friendly, 3)
+            .mine(1, 100.0, 500.0, Owner::Friendly, 3)
+            .mine(2, 100.0, 800.0, Owner::Friendly, 3)
+            .empty_site(3, 500.0, 500.0)
+            .empty_site(4, 330.0, 560.0)
+            .build()
+    };
+
+    let strategy = BehaviorTreeStrategy::new(StrategyParams::default());
+    let context = context_at(700.0);
+    assert!(strategy.get_next_task(&context).get_next_command(&context).to_string().starts_with("MOVE"));
+    assert_eq!(strategy.get_debug_info().unwrap(), "queen/army/build knight barracks/walk");
+
+    // site 4 is closest by now, but the queen arrived at site 3
+    let context = context_at(400.0);
+    assert_eq!(strategy.fallback.get_closest_site_to_build(&context).unwrap().id, 4);
+    assert_eq!(strategy.get_next_task(&context).get_next_command(&context).to_string(), "BUILD 3 BARRACKS-KNIGHT");
+}
+
+#[test]
+fn game_from_context_estimates_hidden_resources() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 500.0)
+        .empty_site(0, 300.0, 300.0)
+        .mine(1, 1620.0, 700.0, Owner::Enemy, -1)
+        .gold(150)
+        .build();
+    context.site_by_id_mut(1).gold = -1;
+    context.site_by_id_mut(1).max_mining_rate = -1;
+
+    let game = Game::from_context(&context);
+
+    assert_eq!(game.gold, [150, 100]);
+    assert_eq!(game.units.len(), 2);
+    assert_eq!((game.sites[1].gold, game.sites[1].max_mining_rate), (200, 3));
+    assert_eq!(game.sites[1].mine().mining_rate, 3);
+}
+
+#[test]
+fn search_avoids_building_under_enemy_tower() {
+    let context = ContextBuilder::new()
+        .queen(500.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 500.0)
+        .empty_site(0, 200.0, 500.0)
+        .empty_site(1, 800.0, 500.0)
+        .site(2, 1000.0, 500.0, Structure::Tower(TowerStructure { owner: Owner::Enemy, health: 800, attack_radius: 500 }))
+        .empty_site(3, 200.0, 200.0)
+        .empty_site(4, 200.0, 800.0)
+        .empty_site(5, 450.0, 200.0)
+        .empty_site(6, 450.0, 800.0)
+        .build();
+
+    let candidates = [0, 1].map(|site_id| MacroAction::Build(BuildCommand { site_id, build_type: BuildType::Tower }));
+    let best = MonteCarloSearch::new(1).best_action(&context, &candidates, &TurnTimer::new());
+
+    assert!(matches!(best, Some(MacroAction::Build(BuildCommand { site_id: 0, .. }))));
+    assert!(MonteCarloSearch::new(1).best_action(&context, &[], &TurnTimer::new()).is_none());
+}
+
+#[test]
+fn search_strategy_plans_walk_and_build_on_searched_site() {
+    let context = ContextBuilder::new()
+        .queen(300.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1620.0, 500.0)
+        .mine(0, 100.0, 200.0, Owner::Friendly, 1)
+        .mine(1, 100.0, 500.0, Owner::Friendly, 1)
+        .mine(2, 100.0, 800.0, Owner::Friendly, 1)
+        .empty_site(3, 500.0, 300.0)
+        .empty_site(4, 500.0, 700.0)
+        .build();
+
+    let strategy = SearchStrategy::new(StrategyParams::default());
+    assert_eq!(strategy.get_search_candidates(&context).len(), 2);
+
+    let plan = strategy.get_next_plan(&context, &TurnTimer::new());
+    assert_eq!(plan.len(), 2);
+    assert!(plan[0].get_next_command(&context).to_string().starts_with("MOVE"));
+    assert!(plan[1].get_next_command(&context).to_string().ends_with("BARRACKS-KNIGHT"));
+}
+
+/// Knights closing in on a queen next to a friendly tower while a barracks is due.
+fn knights_near_tower_context(tower_owner: Owner) -> Context {
+    ContextBuilder::new()
+        .queen(600.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1620.0, 500.0)
+        .enemy_knight(900.0, 500.0)
+        .enemy_knight(920.0, 520.0)
+        .tower(0, 350.0, 500.0, tower_owner, 300)
+        .mine(1, 100.0, 200.0, Owner::Friendly, 1)
+        .mine(2, 100.0, 500.0, Owner::Friendly, 1)
+        .mine(3, 100.0, 800.0, Owner::Friendly, 1)
+        .empty_site(4, 600.0, 300.0)
+        .empty_site(5, 600.0, 700.0)
+        .build()
+}
+
+#[test]
+fn search_strategy_plans_move_toward_chosen_target() {
+    let context = knights_near_tower_context(Owner::Friendly);
+    let strategy = SearchStrategy::new(StrategyParams::default());
+    let target = strategy.get_search_candidates(&context).into_iter()
+        .find_map(|action| match action {
+            MacroAction::Move(position) => Some(position),
+            MacroAction::Build(_) => None,
+        })
+        .unwrap();
+    assert!(context.towers_in_range(target, Owner::Friendly) > 0);
+
+    let plan = strategy.plan_action(&context, MacroAction::Move(target));
+    assert_eq!(plan.len(), 1);
+    assert!(!plan[0].is_task_done(&context));
+
+    let QueenCommand::Move(step) = plan[0].get_next_command(&context) else {
+        panic!("queen does not move");
+    };
+    let queen_position = context.friendly_queen().position;
+    assert!((step - target).len() < (queen_position - target).len());
+}
+
+#[test]
+fn search_strategy_moves_only_to_covered_positions() {
+    let context = knights_near_tower_context(Owner::Enemy);
+    let candidates = SearchStrategy::new(StrategyParams::default()).get_search_candidates(&context);
+
+    assert!(!candidates.is_empty());
+    assert!(candidates.iter().all(|action| matches!(action, MacroAction::Build(_))));
+}
+
+#[test]
+fn turn_timer_allows_more_time_on_first_turn() {
+    let mut timer = TurnTimer::with_budgets(Duration::from_millis(1000), Duration::from_millis(50));
+    assert_eq!(timer.budget(), Duration::from_millis(1000));
+
+    timer.start_turn();
+    assert_eq!(timer.budget(), Duration::from_millis(1000));
+
+    timer.start_turn();
+    assert_eq!(timer.budget(), Duration::from_millis(50));
+    assert!(timer.remaining() <= Duration::from_millis(50));
+    assert!(timer.elapsed() < Duration::from_millis(50));
+}
+
+#[test]
+fn search_stops_when_turn_time_runs_out() {
+    let context = ContextBuilder::new()
+        .queen(300.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1620.0, 500.0)
+        .mine(0, 100.0, 200.0, Owner::Friendly, 1)
+        .mine(1, 100.0, 500.0, Owner::Friendly, 1)
+        .mine(2, 100.0, 800.0, Owner::Friendly, 1)
+        .empty_site(3, 500.0, 300.0)
+        .empty_site(4, 500.0, 700.0)
+        .build();
+    let timer = TurnTimer::with_budgets(Duration::ZERO, Duration::ZERO);
+
+    let strategy = SearchStrategy::new(StrategyParams::default());
+    let candidates = strategy.get_search_candidates(&context);
+
+    assert!(MonteCarloSearch::new(1).best_action(&context, &candidates, &timer).is_none());
+    assert!(!strategy.get_next_plan(&context, &timer).is_empty());
+}
+
+/// Plays a turn of the engine, the enemy queen waits and nobody trains unless given.
+fn play_engine_turn(game: &mut Game, queen_command: QueenCommand, train_site_ids: Vec<i32>) {
+    game.play_commands([
+        Some((queen_command, TrainCommand { site_ids: train_site_ids })),
+        Some((QueenCommand::Wait, TrainCommand { site_ids: Vec::new() })),
+    ]);
+}
+
+#[test]
+fn engine_clamps_queen_to_map_and_resolves_collisions() {
+    let context = ContextBuilder::new()
+        .queen(40.0, 40.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1000.0, 500.0)
+        .unit(UnitType::Knight, Owner::Friendly, 1010.0, 500.0)
+        .empty_site(0, 600.0, 800.0)
+        .build();
+    let mut game = Game::from_context(&context);
+
+    play_engine_turn(&mut game, QueenCommand::Move(Vector2 { x: 0.0, y: 0.0 }), Vec::new());
+    assert_eq!((game.queen(0).position.x, game.queen(0).position.y), (30.0, 30.0));
+
+    // the heavy queen barely moves, the knight is pushed out of her
+    let knight = game.units.iter().find(|unit| unit.unit_type == UnitType::Knight).unwrap();
+    assert!((knight.position - game.queen(1).position).len() >= 50.0 - 1.0);
+    assert!((game.queen(1).position - Vector2 { x: 1000.0, y: 500.0 }).len() <= 2.0);
+
+    // walking into a site stops at its edge
+    game.units[0].position = Vector2 { x: 600.0, y: 650.0 };
+    play_engine_turn(&mut game, QueenCommand::Move(Vector2 { x: 600.0, y: 800.0 }), Vec::new());
+    assert_eq!((game.queen(0).position.x, game.queen(0).position.y), (600.0, 710.0));
+}
+
+#[test]
+fn engine_melts_towers_and_recomputes_radius() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 900.0)
+        .tower(0, 500.0, 500.0, Owner::Friendly, 400)
+        .site(1, 900.0, 500.0, Structure::Tower(TowerStructure { owner: Owner::Friendly, health: 4, attack_radius: 100 }))
+        .build();
+    let mut game = Game::from_context(&context);
+
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+
+    let tower = game.sites[0].tower();
+    assert_eq!(tower.health, 400 - TOWER_MELT_RATE);
+    assert_eq!(tower.attack_radius, tower_attack_radius(396, 60));
+    assert!(game.sites[0].tower_health_for_radius(tower.attack_radius) <= 396);
+    assert!(game.sites[0].tower_health_for_radius(tower.attack_radius + 1) > 396);
+    assert!(matches!(game.sites[1].structure, Structure::NoStructure));
+}
+
+#[test]
+fn engine_mines_gold_until_site_is_depleted() {
+    let mut context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 900.0)
+        .mine(0, 500.0, 500.0, Owner::Friendly, 3)
+        .gold(0)
+        .build();
+    context.site_by_id_mut(0).gold = 5;
+    let mut game = Game::from_context(&context);
+
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+    assert_eq!((game.gold[0], game.sites[0].gold), (3, 2));
+
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+    assert_eq!((game.gold[0], game.sites[0].gold), (5, 0));
+    assert!(matches!(game.sites[0].structure, Structure::NoStructure));
+}
+
+#[test]
+fn engine_trains_for_cost_and_spawns_after_delay() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 900.0)
+        .barracks(0, 500.0, 500.0, Owner::Friendly, BarracksType::Knight)
+        .barracks(1, 500.0, 800.0, Owner::Friendly, BarracksType::Giant)
+        .gold(100)
+        .build();
+    let mut game = Game::from_context(&context);
+    let knight_count = |game: &Game| game.units.iter().filter(|unit| unit.unit_type == UnitType::Knight).count();
+
+    // the giant is too expensive after the knights
+    play_engine_turn(&mut game, QueenCommand::Wait, vec![0, 1]);
+    assert_eq!(game.gold[0], 100 - BarracksType::Knight.cost_to_train());
+    assert_eq!(game.sites[1].barracks().turns_to_train, 0);
+
+    for _ in 1..BarracksType::Knight.turns_to_train() {
+        assert_eq!(knight_count(&game), 0);
+        play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+    }
+
+    assert_eq!(knight_count(&game), BarracksType::Knight.units_per_training());
+    assert_eq!(game.sites[0].barracks().turns_to_train, 0);
+}
+
+#[test]
+fn engine_applies_creep_damage() {
+    let context = ContextBuilder::new()
+        .queen(300.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 900.0)
+        .enemy_knight(350.0, 500.0)
+        .unit(UnitType::Archer, Owner::Friendly, 350.0, 700.0)
+        .unit(UnitType::Giant, Owner::Friendly, 1400.0, 500.0)
+        .tower(0, 1500.0, 500.0, Owner::Enemy, 150)
+        .build();
+    let mut game = Game::from_context(&context);
+    let health = |game: &Game, unit_type: UnitType| game.units.iter().find(|unit| unit.unit_type == unit_type).unwrap().health;
+
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+
+    assert_eq!(game.queen(0).health, UnitType::Queen.max_health() - KNIGHT_DAMAGE);
+    // creeps also lose a health point every turn
+    assert_eq!(health(&game, UnitType::Knight), UnitType::Knight.max_health() - ARCHER_DAMAGE - 1);
+    assert_eq!(game.sites[0].tower().health, 400 - GIANT_BUST_RATE - TOWER_MELT_RATE);
+    assert!(health(&game, UnitType::Giant) < UnitType::Giant.max_health() - 1);
+
+    // archers deal more damage to giants
+    game.units.retain(|unit| unit.unit_type != UnitType::Knight);
+    game.units.push(Unit { position: Vector2 { x: 350.0, y: 500.0 }, owner: Owner::Enemy, unit_type: UnitType::Giant, health: 200 });
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+
+    let enemy_giant = game.units.iter().find(|unit| unit.owner == Owner::Enemy && unit.unit_type == UnitType::Giant).unwrap();
+    assert_eq!(enemy_giant.health, 200 - ARCHER_DAMAGE_TO_GIANTS - 1);
+}
+
+#[test]
+fn engine_decides_by_queen_health_at_turn_limit() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 100.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 900.0)
+        .build();
+    let mut game = Game::from_context(&context);
+    game.turn = MAX_TURNS - 1;
+    game.units[0].health = 50;
+
+    assert!(!game.is_over());
+    play_engine_turn(&mut game, QueenCommand::Wait, Vec::new());
+    assert!(game.is_over());
+
+    let result = game.result();
+    assert_eq!(result.turns, MAX_TURNS);
+    assert_eq!(result.winner, Some(1));
+    assert_eq!(result.queen_health, [50, 100]);
+}
+
+#[test]
+fn engine_renders_turn_input_for_each_player() {
+    let context = ContextBuilder::new()
+        .queen(100.0, 500.0)
+        .unit(UnitType::Queen, Owner::Enemy, 1820.0, 500.0)
+        .mine(0, 300.0, 500.0, Owner::Friendly, 2)
+        .tower(1, 1620.0, 500.0, Owner::Enemy, 300)
+        .gold(120)
+        .build();
+    let game = Game::from_context(&context);
+
+    assert_eq!(game.turn_lines(0), vec![
+        "120 -1",
+        "0 200 3 0 0 2 -1",
+        "1 -1 -1 1 1 400 300",
+        "2",
+        "100 500 0 -1 100",
+        "1820 500 1 -1 100",
+    ]);
+    assert_eq!(game.turn_lines(1), vec![
+        "100 -1",
+        "0 -1 -1 0 1 -1 -1",
+        "1 200 3 1 0 400 300",
+        "2",
+        "100 500 1 -1 100",
+        "1820 500 0 -1 100",
+    ]);
+}
+
+/// Context with a single site 0 as parsed from the initialization input.
+fn parsed_context() -> Context {
+    parse_init(&mut "1\n0 300 400 60\n".as_bytes()).unwrap()
+}
+
+#[test]
+fn protocol_rejects_wrong_token_count() {
+    let error = parse_init(&mut "1\n0 300 400\n".as_bytes()).err().unwrap();
+    assert!(matches!(error, ProtocolError::BadTokenCount { expected: 4, .. }));
+
+    let error = parse_turn(&mut "100 -1\n0 -1 -1 -1 -1 -1\n".as_bytes(), &mut parsed_context()).unwrap_err();
+    assert!(matches!(error, ProtocolError::BadTokenCount { expected: 7, .. }));
+}
+
+#[test]
+fn protocol_rejects_non_numeric_field() {
+    let error = parse_init(&mut "1\n0 300 x 60\n".as_bytes()).err().unwrap();
+    assert!(matches!(error, ProtocolError::InvalidNumber(token) if token == "x"));
+}
+
+#[test]
+fn protocol_rejects_unknown_ids() {
+    let parse = |input: &str| parse_turn(&mut input.as_bytes(), &mut parsed_context()).unwrap_err();
+
+    assert!(matches!(parse("100 -1\n0 -1 -1 3 0 0 0\n0\n"), ProtocolError::UnknownStructureType(3)));
+    assert!(matches!(parse("100 -1\n0 -1 -1 1 2 200 300\n0\n"), ProtocolError::UnknownOwner(2)));
+    assert!(matches!(parse("100 -1\n0 -1 -1 2 0 0 5\n0\n"), ProtocolError::UnknownBarracksParam(5)));
+    assert!(matches!(parse("100 -1\n7 -1 -1 -1 -1 -1 -1\n0\n"), ProtocolError::UnknownSite(7)));
+    assert!(matches!(parse("100 -1\n0 -1 -1 -1 -1 -1 -1\n1\n100 100 0 4 100\n"), ProtocolError::UnknownUnitType(4)));
+}
+
+#[test]
+fn protocol_reports_truncated_input() {
+    let error = parse_init(&mut "2\n0 300 400 60\n".as_bytes()).err().unwrap();
+    assert!(matches!(error, ProtocolError::UnexpectedEof));
+
+    let error = parse_turn(&mut "100 -1\n0 -1 -1 -1 -1 -1 -1\n".as_bytes(), &mut parsed_context()).unwrap_err();
+    assert!(matches!(error, ProtocolError::UnexpectedEof));
+
+    // no input at all before a turn ends the game
+    assert!(!parse_turn(&mut "".as_bytes(), &mut parsed_context()).unwrap());
+}
+
+/// Stderr dump of the first turns of a seeded game as the bot writes it, with other debug output.
+fn recorded_dump(turns: usize) -> String {
+    let mut game = Game::new(7);
+    let mut dump = String::new();
+    let mut lines = game.init_lines();
+
+    for _ in 0..turns {
+        lines.extend(game.turn_lines(0));
+        dump.push_str(&lines.iter().map(|line| format!("{};", line)).collect::<String>());
+        dump.push_str("\ndebug output\n");
+        lines.clear();
+        game.play_turn([("WAIT", "TRAIN"), ("WAIT", "TRAIN")]);
+    }
+
+    dump
+}
+
+#[test]
+fn replay_counts_turns_and_mismatches() {
+    let dump = recorded_dump(3);
+    let mut printed = Vec::new();
+    let summary = replay_log(&dump, None, Box::new(DefaultStrategy::default()), &mut printed);
+
+    assert_eq!((summary.turns, summary.mismatches), (3, 0));
+    assert!(summary.error.is_none());
+
+    // the commands replayed, with the second turn played differently
+    let mut played: Vec<String> = String::from_utf8(printed).unwrap().lines()
+        .flat_map(|line| line.split_once(": ").unwrap().1.split(" | ").map(String::from).collect::<Vec<_>>())
+        .collect();
+    played[2] = String::from("WAIT");
+
+    let summary = replay_log(&dump, Some(&played.join("\n")), Box::new(DefaultStrategy::default()), &mut Vec::new());
+    assert_eq!((summary.turns, summary.mismatches), (3, 1));
+}
+
+#[test]
+fn replay_reports_malformed_dump() {
+    let dump = recorded_dump(1).replacen(";0 ", ";0 x ", 1);
+    let summary = replay_log(&dump, None, Box::new(DefaultStrategy::default()), &mut Vec::new());
+
+    assert_eq!(summary.turns, 0);
+    assert!(matches!(summary.error, Some(ProtocolError::BadTokenCount { .. })));
+}
+
+#[test]
+fn bundler_strips_comments_and_indentation() {
+    let source = "/**\n * Doc.\n **/\nfn main() {\n    // note\n    let url = \"http://host\"; /* gone */\n\n    let slash = '/'; /// doc\n    let text = \"a\nb\";\n}\n";
+    let stripped = strip_comments(source);
+
+    assert_eq!(stripped, "fn main() {\nlet url = \"http://host\";\nlet slash = '/';\nlet text = \"a\\nb\";\n}\n");
+}
+
+#[test]
+fn bundler_compacts_whitespace_between_tokens() {
+    let source = "let x: Vec<i32> = f(a, -b);\nif x >= y && !z { s = \"a  b\"; c = ' '; }\nimpl<'a> T for &'a str {}\n";
+    let compacted = compact_whitespace(source);
+
+    assert_eq!(compacted, "let x:Vec<i32> =f(a,-b);\nif x>=y&& !z{s=\"a  b\";c=' ';}\nimpl<'a>T for&'a str{}\n");
+}
+
+#[test]
+fn bundle_fits_submission_limit_without_offline_modules() {
+    let main_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("main.rs");
+    let source = bundle(&main_path, "code_royale").unwrap();
+
+    assert!(source.chars().count() <= MAX_SUBMISSION_CHARS);
+    for module in ["behavior", "bundler", "referee", "replay"] {
+        assert!(!source.contains(&format!("pub mod {} {{", module)), "{} is bundled", module);
+    }
+
+    // the search strategy the submission runs looks ahead with the engine
+    for module in ["engine", "random", "search"] {
+        assert!(source.contains(&format!("pub mod {} {{", module)), "{} is not bundled", module);
+    }
+    assert!(source.contains("\"search\"=>"));
+}
+
+#[test]
+fn bot_process_times_out_after_turn_time() {
+    let mut bot = BotProcess::spawn("cat").unwrap();
+    bot.send_lines(&[String::from("init")]).unwrap();
+    assert_eq!(bot.read_line().unwrap(), "init");
+
+    // answered the first turn, but not the second one
+    bot.send_lines(&[String::from("WAIT")]).unwrap();
+    assert_eq!(bot.read_line().unwrap(), "WAIT");
+    bot.send_lines(&[]).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(bot.read_line().unwrap_err().kind(), io::ErrorKind::TimedOut);
+    assert!(start.elapsed() < FIRST_TURN_TIME);
+}
+
+#[test]
+fn referee_counts_timeout_as_loss() {
+    let players = [PlayerSpec::Process(String::from("sleep 10")), PlayerSpec::parse("strategy:default").unwrap()];
+    let result = play_game([&players[0], &players[1]], 1).unwrap();
+
+    assert_eq!(result.winner, Some(1));
+    assert_eq!(result.turns, 1);
+}
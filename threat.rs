@@ -0,0 +1,149 @@
+use crate::context::Context;
+use crate::owner::Owner;
+use crate::point::Vector2;
+use crate::site::Site;
+use crate::unit::{UnitType, ARCHER_DAMAGE, ARCHER_DAMAGE_TO_GIANTS, CONTACT_RANGE};
+
+/// Number of turns the enemy units are advanced.
+pub const PREDICTION_TURNS: usize = 10;
+
+#[derive(Copy, Clone)]
+pub struct PredictedUnit {
+    pub position: Vector2,
+    pub unit_type: UnitType,
+    pub health: i32,
+}
+
+/**
+ * Advances the enemy units a few turns ahead using their speeds and targeting rules:
+ * knights chase our queen, giants walk to our closest tower and archers to our closest creep
+ * or back to their queen. Creeps age by one health per turn, our archers close in on the
+ * enemy creeps and shoot them, units dying on the way are dropped. Our other units are
+ * assumed to stand still.
+ **/
+pub struct ThreatPredictor {
+    /// enemy units after 0, 1, ... turns
+    frames: Vec<Vec<PredictedUnit>>,
+}
+
+impl ThreatPredictor {
+    pub fn new(context: &Context, turns: usize) -> ThreatPredictor {
+        let predicted = |owner: Owner| -> Vec<PredictedUnit> {
+            context.units.iter()
+                .filter(|unit| unit.owner == owner && unit.unit_type != UnitType::Queen)
+                .map(|unit| PredictedUnit { position: unit.position, unit_type: unit.unit_type, health: unit.health })
+                .collect()
+        };
+
+        let mut units = predicted(Owner::Enemy);
+        let mut archers: Vec<PredictedUnit> = predicted(Owner::Friendly).into_iter()
+            .filter(|unit| unit.unit_type == UnitType::Archer)
+            .collect();
+
+        let mut frames = vec![units.clone()];
+
+        for _ in 0..turns {
+            for unit in &mut units {
+                if let Some((target, stop_dist)) = ThreatPredictor::target(context, unit) {
+                    ThreatPredictor::advance(unit, target, stop_dist);
+                }
+            }
+
+            for archer in &mut archers {
+                let target = units.iter().min_by_key(|unit| (unit.position - archer.position).len() as i32);
+
+                if let Some(target) = target {
+                    let target_position = target.position;
+                    ThreatPredictor::advance(archer, target_position, UnitType::Archer.attack_range());
+                }
+            }
+
+            for archer in &archers {
+                let target = units.iter_mut()
+                    .map(|unit| {
+                        let gap = (unit.position - archer.position).len() - archer.unit_type.radius() - unit.unit_type.radius();
+                        (unit, gap)
+                    })
+                    .filter(|(_, gap)| *gap <= UnitType::Archer.attack_range())
+                    .min_by_key(|(_, gap)| *gap as i32);
+
+                if let Some((unit, _)) = target {
+                    unit.health -= if unit.unit_type == UnitType::Giant { ARCHER_DAMAGE_TO_GIANTS } else { ARCHER_DAMAGE };
+                }
+            }
+
+            for unit in units.iter_mut().chain(archers.iter_mut()) {
+                unit.health -= 1;
+            }
+
+            units.retain(|unit| unit.health > 0);
+            archers.retain(|archer| archer.health > 0);
+            frames.push(units.clone());
+        }
+
+        ThreatPredictor { frames }
+    }
+
+    fn advance(unit: &mut PredictedUnit, target: Vector2, stop_dist: f64) {
+        let delta = target - unit.position;
+        let step = (delta.len() - stop_dist).min(unit.unit_type.speed());
+
+        if step > 0.0 {
+            unit.position = unit.position + delta.norm().mul(step);
+        }
+    }
+
+    fn target(context: &Context, unit: &PredictedUnit) -> Option<(Vector2, f64)> {
+        let radius = unit.unit_type.radius();
+
+        match unit.unit_type {
+            UnitType::Queen => None,
+            UnitType::Knight => {
+                let queen = context.friendly_queen();
+                Some((queen.position, radius + queen.unit_type.radius()))
+            },
+            UnitType::Giant => {
+                context.sites.iter()
+                    .filter(|site| site.is_tower() && site.tower().owner == Owner::Friendly)
+                    .min_by_key(|site| (site.position - unit.position).len() as i32)
+                    .map(|site| (site.position, radius + site.radius as f64))
+            },
+            UnitType::Archer => {
+                let friendly_creep = context.units.iter()
+                    .filter(|other| other.owner == Owner::Friendly && other.unit_type != UnitType::Queen)
+                    .min_by_key(|other| (other.position - unit.position).len() as i32);
+
+                match friendly_creep {
+                    Some(other) => Some((other.position, UnitType::Archer.attack_range())),
+                    None => context.units.iter()
+                        .find(|other| other.owner == Owner::Enemy && other.unit_type == UnitType::Queen)
+                        .map(|queen| (queen.position, radius + queen.unit_type.radius())),
+                }
+            },
+        }
+    }
+
+    /// Enemy units as predicted after the given number of turns, capped at the prediction horizon.
+    pub fn units_after(&self, turns: usize) -> &[PredictedUnit] {
+        &self.frames[turns.min(self.frames.len() - 1)]
+    }
+
+    /// First turn an enemy unit of the type gets within `reach` of the position, counting from the unit edges.
+    pub fn turns_until_reached(&self, unit_type: UnitType, position: Vector2, reach: f64) -> Option<usize> {
+        self.frames.iter().position(|units| {
+            units.iter().any(|unit| {
+                unit.unit_type == unit_type
+                    && (unit.position - position).len() - unit.unit_type.radius() <= reach + CONTACT_RANGE
+            })
+        })
+    }
+
+    pub fn turns_until_knight_reaches_queen(&self, context: &Context) -> Option<usize> {
+        let queen = context.friendly_queen();
+        self.turns_until_reached(UnitType::Knight, queen.position, queen.unit_type.radius())
+    }
+
+    pub fn turns_until_knight_reaches_site(&self, site: &Site) -> Option<usize> {
+        self.turns_until_reached(UnitType::Knight, site.position, site.radius as f64)
+    }
+}
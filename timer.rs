@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+/// Response time CodinGame allows per turn.
+pub const TURN_TIME: Duration = Duration::from_millis(50);
+
+/// Response time of the first turn, which leaves room for initialization.
+pub const FIRST_TURN_TIME: Duration = Duration::from_millis(1000);
+
+/// Turns taking longer than this are reported on stderr unless configured otherwise.
+pub const DEFAULT_TIME_WARNING: Duration = Duration::from_millis(40);
+
+/**
+ * Time spent on the current turn and what is left of its budget, started by the game loop as soon
+ * as the input of a turn arrives and passed on to the strategy. Planners query the remaining time
+ * to stop searching early.
+ **/
+pub struct TurnTimer {
+    start: Instant,
+    first_turn_budget: Duration,
+    turn_budget: Duration,
+    turns_started: usize,
+}
+
+impl TurnTimer {
+    pub fn new() -> TurnTimer {
+        TurnTimer::with_budgets(FIRST_TURN_TIME, TURN_TIME)
+    }
+
+    pub fn with_budgets(first_turn_budget: Duration, turn_budget: Duration) -> TurnTimer {
+        TurnTimer {
+            start: Instant::now(),
+            first_turn_budget,
+            turn_budget,
+            turns_started: 0,
+        }
+    }
+
+    pub fn start_turn(&mut self) {
+        self.start = Instant::now();
+        self.turns_started += 1;
+    }
+
+    /// Time allowed for the current turn, the first turn's budget until a turn is started.
+    pub fn budget(&self) -> Duration {
+        if self.turns_started <= 1 { self.first_turn_budget } else { self.turn_budget }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Time left of the budget of the current turn, zero once it is used up.
+    pub fn remaining(&self) -> Duration {
+        self.budget().saturating_sub(self.elapsed())
+    }
+}
+
+impl Default for TurnTimer {
+    fn default() -> Self {
+        TurnTimer::new()
+    }
+}
@@ -0,0 +1,87 @@
+use std::env;
+
+use code_royale::engine::GameResult;
+use code_royale::referee::{play_series, PlayerSpec};
+
+#[derive(Default)]
+struct BotStats {
+    wins: usize,
+    losses: usize,
+    draws: usize,
+    total_gold: i32,
+    total_structures: usize,
+}
+
+impl BotStats {
+    fn add(&mut self, result: &GameResult, player: usize) {
+        match result.winner {
+            Some(winner) if winner == player => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+
+        self.total_gold += result.gold[player];
+        self.total_structures += result.structures[player];
+    }
+}
+
+/**
+ * Plays games between two bots on consecutive seeds with swapped sides, see `play_series`.
+ * Bots are command lines or in-process strategies, see `PlayerSpec::parse`.
+ * Usage: tournament <bot> <bot> [games] [first seed]
+ **/
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 3 {
+        eprintln!("usage: {} <bot> <bot> [games] [first seed]", args[0]);
+        std::process::exit(2);
+    }
+
+    let bots = [args[1].as_str(), args[2].as_str()];
+    let game_count = args.get(3).map_or(100, |arg| arg.parse::<usize>().expect("games must be a number"));
+    let first_seed = args.get(4).map_or(1, |arg| arg.parse::<u64>().expect("seed must be a number"));
+
+    let players = bots.map(|spec| {
+        PlayerSpec::parse(spec).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        })
+    });
+
+    let results = play_series([&players[0], &players[1]], game_count, first_seed).expect("failed to run bots");
+
+    let mut stats = [BotStats::default(), BotStats::default()];
+    let mut total_turns = 0;
+    let mut games_played = 0;
+
+    for (swapped, result) in results {
+        // player index of the first bot in this game
+        let first_player = if swapped { 1 } else { 0 };
+
+        stats[0].add(&result, first_player);
+        stats[1].add(&result, 1 - first_player);
+        total_turns += result.turns;
+        games_played += 1;
+    }
+
+    if games_played == 0 {
+        return;
+    }
+
+    let games = games_played as f64;
+    println!("{} games, average length {:.1} turns", games_played, total_turns as f64 / games);
+
+    for (bot, stats) in bots.iter().zip(&stats) {
+        println!(
+            "{}: {} wins, {} losses, {} draws ({:.1}% win rate), average gold {:.1}, average structures {:.1}",
+            bot,
+            stats.wins,
+            stats.losses,
+            stats.draws,
+            100.0 * stats.wins as f64 / games,
+            stats.total_gold as f64 / games,
+            stats.total_structures as f64 / games
+        );
+    }
+}
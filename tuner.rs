@@ -0,0 +1,130 @@
+use std::env;
+use std::fs;
+
+use code_royale::params::StrategyParams;
+use code_royale::random::Random;
+use code_royale::referee::{play_series, PlayerSpec};
+
+/// Tuned parameter with the step size used to normalize it and its allowed range.
+struct Knob {
+    key: &'static str,
+    step: f64,
+    min: f64,
+    max: f64,
+}
+
+const KNOBS: [Knob; 14] = [
+    Knob { key: "min_mine_count", step: 1.0, min: 0.0, max: 8.0 },
+    Knob { key: "max_mine_count", step: 1.0, min: 0.0, max: 10.0 },
+    Knob { key: "min_tower_count", step: 1.0, min: 0.0, max: 6.0 },
+    Knob { key: "knight_danger_turns", step: 1.0, min: 0.0, max: 10.0 },
+    Knob { key: "build_radius", step: 100.0, min: 300.0, max: 2000.0 },
+    Knob { key: "tower_radius_goal", step: 25.0, min: 200.0, max: 600.0 },
+    Knob { key: "rush_warning_turns", step: 1.0, min: 0.0, max: 20.0 },
+    Knob { key: "archer_knight_count", step: 1.0, min: 1.0, max: 12.0 },
+    Knob { key: "tower_upkeep_radius", step: 25.0, min: 150.0, max: 500.0 },
+    Knob { key: "tower_upkeep_travel_turns", step: 1.0, min: 0.0, max: 20.0 },
+    Knob { key: "queen_turn_value", step: 5.0, min: 0.0, max: 100.0 },
+    Knob { key: "mine_safety_value", step: 5.0, min: 0.0, max: 100.0 },
+    Knob { key: "tower_frontier_value", step: 5.0, min: 0.0, max: 100.0 },
+    Knob { key: "plan_length", step: 1.0, min: 1.0, max: 5.0 },
+];
+
+// SPSA gain sequences, see Spall, "Implementation of the Simultaneous Perturbation Algorithm"
+const LEARNING_RATE: f64 = 4.0;
+const LEARNING_RATE_OFFSET: f64 = 10.0;
+const PERTURBATION: f64 = 1.0;
+
+fn params_at(baseline: &StrategyParams, theta: &[f64]) -> StrategyParams {
+    let mut params = baseline.clone();
+
+    for (knob, value) in KNOBS.iter().zip(theta) {
+        params.set(knob.key, (value * knob.step).clamp(knob.min, knob.max)).unwrap();
+    }
+
+    params
+}
+
+/// Win rate of the default strategy with the given parameters against the baseline.
+fn win_rate(params: &StrategyParams, baseline: &StrategyParams, game_count: usize, first_seed: u64) -> f64 {
+    let candidate = PlayerSpec::Strategy(String::from("default"), params.clone());
+    let baseline = PlayerSpec::Strategy(String::from("default"), baseline.clone());
+    let results = play_series([&candidate, &baseline], game_count, first_seed).unwrap();
+
+    let score: f64 = results.iter()
+        .map(|(swapped, result)| {
+            let candidate_player = if *swapped { 1 } else { 0 };
+
+            match result.winner {
+                Some(winner) if winner == candidate_player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            }
+        })
+        .sum();
+
+    score / results.len() as f64
+}
+
+/**
+ * Tunes the parameters of the default strategy with SPSA in self-play against the baseline
+ * parameters (defaults overridden by environment variables). The estimate is written after each
+ * iteration and evaluated against the baseline on fresh seeds at the end.
+ * Usage: tuner <output file> [iterations] [games per evaluation]
+ **/
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 2 {
+        eprintln!("usage: {} <output file> [iterations] [games per evaluation]", args[0]);
+        std::process::exit(2);
+    }
+
+    let output_path = &args[1];
+    let iterations = args.get(2).map_or(50, |arg| arg.parse::<usize>().expect("iterations must be a number"));
+    let game_count = args.get(3).map_or(40, |arg| arg.parse::<usize>().expect("games must be a number"));
+
+    let baseline = StrategyParams::load(None).unwrap();
+    let mut random = Random::new(1);
+    let mut theta: Vec<f64> = KNOBS.iter()
+        .map(|knob| baseline.get(knob.key).unwrap() / knob.step)
+        .collect();
+
+    for iteration in 0..iterations {
+        let k = (iteration + 1) as f64;
+        let learning_rate = LEARNING_RATE / (k + LEARNING_RATE_OFFSET).powf(0.602);
+        let perturbation = PERTURBATION / k.powf(0.101);
+        let first_seed = 1 + (iteration * game_count) as u64;
+
+        let delta: Vec<f64> = KNOBS.iter()
+            .map(|_| if random.range(0, 1) == 0 { 1.0 } else { -1.0 })
+            .collect();
+
+        let theta_plus: Vec<f64> = theta.iter().zip(&delta).map(|(t, d)| t + perturbation * d).collect();
+        let theta_minus: Vec<f64> = theta.iter().zip(&delta).map(|(t, d)| t - perturbation * d).collect();
+
+        let win_rate_plus = win_rate(&params_at(&baseline, &theta_plus), &baseline, game_count, first_seed);
+        let win_rate_minus = win_rate(&params_at(&baseline, &theta_minus), &baseline, game_count, first_seed);
+
+        for ((t, d), knob) in theta.iter_mut().zip(&delta).zip(&KNOBS) {
+            let gradient = (win_rate_plus - win_rate_minus) / (2.0 * perturbation * d);
+            *t = (*t + learning_rate * gradient).clamp(knob.min / knob.step, knob.max / knob.step);
+        }
+
+        println!("iteration {}: win rates {:.3} / {:.3}", iteration, win_rate_plus, win_rate_minus);
+        write_params(output_path, &params_at(&baseline, &theta), &format!("iteration {} of {}, not evaluated yet", iteration + 1, iterations));
+    }
+
+    // the perturbed samples are noisy, only the estimate itself is evaluated on seeds not used for tuning
+    let tuned = params_at(&baseline, &theta);
+    let first_seed = 1 + (iterations * game_count) as u64;
+    let tuned_win_rate = win_rate(&tuned, &baseline, game_count, first_seed);
+
+    write_params(output_path, &tuned, &format!("win rate {:.3} against baseline", tuned_win_rate));
+    println!("tuned win rate {:.3}:\n{}", tuned_win_rate, tuned.to_key_values());
+}
+
+fn write_params(path: &str, params: &StrategyParams, comment: &str) {
+    let text = format!("# {}\n{}", comment, params.to_key_values());
+    fs::write(path, text).expect("failed to write parameters");
+}
@@ -1,15 +1,77 @@
-#[derive(PartialEq, Eq)]
-enum UnitType {
+use crate::owner::Owner;
+use crate::point::Vector2;
+
+/// Units touching each other or a site may be apart by this much.
+pub const CONTACT_RANGE: f64 = 5.0;
+
+/// Damage a knight deals to the queen it touches per turn.
+pub const KNIGHT_DAMAGE: i32 = 1;
+
+/// Damage an archer deals per turn, more to giants.
+pub const ARCHER_DAMAGE: i32 = 2;
+pub const ARCHER_DAMAGE_TO_GIANTS: i32 = 10;
+
+/// Tower health a giant touching the tower takes per turn.
+pub const GIANT_BUST_RATE: i32 = 80;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum UnitType {
     Queen,
     Knight,
     Archer,
     Giant,
 }
 
-struct Unit {
-    position: Vector2,
-    owner: Owner,
-    unit_type: UnitType,
-    health: i32,
+#[derive(Copy, Clone)]
+pub struct Unit {
+    pub position: Vector2,
+    pub owner: Owner,
+    pub unit_type: UnitType,
+    pub health: i32,
 }
 
+
+impl UnitType {
+    pub fn speed(&self) -> f64 {
+        match self {
+            UnitType::Queen => 60.0,
+            UnitType::Knight => 100.0,
+            UnitType::Archer => 75.0,
+            UnitType::Giant => 50.0,
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        match self {
+            UnitType::Queen => 30.0,
+            UnitType::Knight => 20.0,
+            UnitType::Archer => 25.0,
+            UnitType::Giant => 40.0,
+        }
+    }
+
+    pub fn mass(&self) -> f64 {
+        match self {
+            UnitType::Queen => 10000.0,
+            UnitType::Knight => 400.0,
+            UnitType::Archer => 900.0,
+            UnitType::Giant => 2000.0,
+        }
+    }
+
+    pub fn max_health(&self) -> i32 {
+        match self {
+            UnitType::Queen => 100,
+            UnitType::Knight => 30,
+            UnitType::Archer => 45,
+            UnitType::Giant => 200,
+        }
+    }
+
+    pub fn attack_range(&self) -> f64 {
+        match self {
+            UnitType::Archer => 200.0,
+            _ => 0.0,
+        }
+    }
+}